@@ -0,0 +1,86 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use prost_build::{Comments, Method, Service, ServiceGenerator};
+
+const PROTOS: &[&str] = &[
+    "protos/comments.proto",
+    "protos/keywords.proto",
+    "protos/wellknown.proto",
+    "protos/nested/deeply/inner/v1.proto",
+    "protos/multi/alpha.proto",
+    "protos/multi/alpha_more.proto",
+    "protos/multi/beta.proto",
+];
+
+fn main() {
+    println!("cargo:rerun-if-changed=protos");
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    assert_refuses_no_package(&out_dir);
+    // Generate every proto with the runtime as a dependency, with it embedded and with the tonic adapters
+    let variants = [("runtime", false, false), ("embedded", true, false), ("tonic", false, true)];
+    for (variant, embed_client, tonic) in &variants {
+        let variant_dir = out_dir.join(variant);
+        fs::create_dir_all(&variant_dir).unwrap();
+        let mut conf = prost_build::Config::new();
+        conf.out_dir(&variant_dir);
+        conf.service_generator(Box::new(generator(*embed_client, *tonic)));
+        conf.compile_protos(PROTOS, &["protos"]).unwrap();
+        generate_without_package(&variant_dir, *embed_client, *tonic);
+    }
+}
+
+fn generator(embed_client: bool, tonic: bool) -> prost_twirp::TwirpServiceGenerator {
+    let mut gen = prost_twirp::TwirpServiceGenerator::new();
+    gen.embed_client = embed_client;
+    gen.tonic = tonic;
+    gen
+}
+
+fn no_comments() -> Comments {
+    Comments { leading_detached: vec![], leading: vec![], trailing: vec![] }
+}
+
+/// prost-build 0.8 refuses to compile `protos/nopackage.proto`, so make sure that is still the case before generating its
+/// code by hand
+fn assert_refuses_no_package(out_dir: &Path) {
+    let mut conf = prost_build::Config::new();
+    conf.out_dir(out_dir);
+    conf.service_generator(Box::new(generator(false, false)));
+    let refused = conf.compile_protos(&["protos/nopackage.proto"], &["protos"]);
+    assert!(refused.is_err_and(|err| err.to_string().contains("package specifier")),
+        "prost-build compiles protos without a package now, add protos/nopackage.proto to PROTOS instead of \
+        generating its code by hand");
+}
+
+/// Drive the generator for `protos/nopackage.proto` the same way prost-build does for protos with a package, naming the
+/// output `_.rs` like newer prost-build versions do for the empty package
+fn generate_without_package(variant_dir: &Path, embed_client: bool, tonic: bool) {
+    let service = Service {
+        name: "Greeter".to_string(),
+        proto_name: "Greeter".to_string(),
+        package: "".to_string(),
+        comments: no_comments(),
+        methods: vec![Method {
+            name: "hello".to_string(),
+            proto_name: "Hello".to_string(),
+            comments: no_comments(),
+            input_type: "()".to_string(),
+            output_type: "()".to_string(),
+            input_proto_type: ".google.protobuf.Empty".to_string(),
+            output_proto_type: ".google.protobuf.Empty".to_string(),
+            options: Default::default(),
+            client_streaming: false,
+            server_streaming: false,
+        }],
+        options: Default::default(),
+    };
+    let mut gen = generator(embed_client, tonic);
+    let mut buf = String::new();
+    gen.generate(service, &mut buf);
+    gen.finalize(&mut buf);
+    gen.finalize_package("", &mut buf);
+    // Named like prost-build names the output of the empty package
+    fs::write(variant_dir.join("_.rs"), buf).unwrap();
+}
@@ -0,0 +1,110 @@
+
+pub trait Greeter {
+    fn hello(&self, i: ::prost_twirp::PTReq<()>) -> ::prost_twirp::PTRes<()>;
+}
+#[allow(dead_code)]
+impl dyn Greeter {
+    /// The fully-qualified protobuf name of the service, without a leading dot
+    pub const FULLY_QUALIFIED_NAME: &'static str = "Greeter";
+    /// The prefix of every route of the service, including the trailing slash
+    pub const PATH_PREFIX: &'static str = "/twirp/Greeter/";
+    /// The static descriptor of the service and its methods
+    pub const DESCRIPTOR: ::prost_twirp::ServiceDescriptor = ::prost_twirp::ServiceDescriptor {
+        package: "",
+        name: "Greeter",
+        full_name: "Greeter",
+        methods: &[
+            ::prost_twirp::MethodDescriptor {
+                name: "hello",
+                proto_name: "Hello",
+                path: "/twirp/Greeter/Hello",
+                input_type: "google.protobuf.Empty",
+                output_type: "google.protobuf.Empty",
+            },
+        ],
+    };
+    pub fn new_client(
+        client: ::hyper::client::Client<
+            ::hyper::client::HttpConnector,
+            ::hyper::body::Body,
+        >,
+        root_url: &str,
+    ) -> Box<dyn Greeter> {
+        Box::new(GreeterClient(::prost_twirp::HyperClient::new(client, root_url)))
+    }
+    pub fn new_server<T: Send + Sync + 'static + Greeter>(
+        v: T,
+    ) -> Box<
+        dyn ::hyper::service::Service<
+            ::hyper::Request<::hyper::body::Body>,
+            Response = ::hyper::Response<::hyper::body::Body>,
+            Error = ::hyper::Error,
+            Future = ::std::pin::Pin<
+                Box<
+                    dyn ::std::future::Future<
+                        Output = ::std::result::Result<
+                            ::hyper::Response<::hyper::body::Body>,
+                            ::hyper::Error,
+                        >,
+                    > + Send,
+                >,
+            >,
+        > + Send,
+    > {
+        Box::new(
+            ::prost_twirp::HyperServer::new(GreeterServer(::std::sync::Arc::new(v))),
+        )
+    }
+}
+pub struct GreeterClient<C = ::hyper::client::HttpConnector>(
+    pub ::prost_twirp::HyperClient<C>,
+);
+impl<C: ::hyper::client::connect::Connect + Clone + Send + Sync + 'static> Greeter
+for GreeterClient<C> {
+    fn hello(&self, i: ::prost_twirp::PTReq<()>) -> ::prost_twirp::PTRes<()> {
+        self.0.go("/twirp/Greeter/Hello", i)
+    }
+}
+pub struct GreeterServer<T: 'static + Greeter>(pub ::std::sync::Arc<T>);
+impl<T: Send + Sync + 'static + Greeter> ::prost_twirp::HyperService
+for GreeterServer<T> {
+    fn handle(
+        &self,
+        req: ::prost_twirp::ServiceRequest<Vec<u8>>,
+    ) -> ::prost_twirp::PTRes<Vec<u8>> {
+        use ::futures::{FutureExt, TryFutureExt};
+        let static_service = self.0.clone();
+        match (req.method.clone(), req.uri.path()) {
+            (::hyper::Method::POST, "/twirp/Greeter/Hello") => {
+                Box::pin(
+                    ::std::future::ready(req.to_proto())
+                        .and_then(move |v| {
+                            static_service
+                                .hello(v)
+                                .map(|r| r.and_then(|v| v.to_proto_raw()))
+                        }),
+                )
+            }
+            (_, path) => {
+                Box::pin(
+                    ::std::future::ready(
+                        Err(
+                            ::prost_twirp::TwirpError::new(
+                                    ::hyper::StatusCode::NOT_FOUND,
+                                    "bad_route",
+                                    &format!("no handler for path {:?}", path),
+                                )
+                                .into(),
+                        ),
+                    ),
+                )
+            }
+        }
+    }
+    fn descriptor(&self) -> Option<&'static ::prost_twirp::ServiceDescriptor> {
+        Some(&<dyn Greeter>::DESCRIPTOR)
+    }
+}
+
+pub type PTReq<I> = ::prost_twirp::PTReq<I>;
+pub type PTRes<O> = ::prost_twirp::PTRes<O>;
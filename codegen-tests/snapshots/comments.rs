@@ -0,0 +1,189 @@
+/// A documented message
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Doc {
+    #[prost(string, tag="1")]
+    pub text: ::prost::alloc::string::String,
+}
+
+// Detached comment that does not belong to anything
+
+/// A `Documented` service with "quotes", back\slashes and
+/// multiple lines.
+///
+/// Including an empty line in between.
+pub trait Documented {
+    /// Leading comment of a method.
+    fn leading(&self, i: ::prost_twirp::PTReq<Doc>) -> ::prost_twirp::PTRes<Doc>;
+    /// Trailing comment of a method.
+    fn trailing(&self, i: ::prost_twirp::PTReq<Doc>) -> ::prost_twirp::PTRes<Doc>;
+    /// Block comment
+    /// spanning lines.
+    fn block(&self, i: ::prost_twirp::PTReq<Doc>) -> ::prost_twirp::PTRes<Doc>;
+    fn undocumented(&self, i: ::prost_twirp::PTReq<Doc>) -> ::prost_twirp::PTRes<Doc>;
+}
+#[allow(dead_code)]
+impl dyn Documented {
+    /// The fully-qualified protobuf name of the service, without a leading dot
+    pub const FULLY_QUALIFIED_NAME: &'static str = "comments.Documented";
+    /// The prefix of every route of the service, including the trailing slash
+    pub const PATH_PREFIX: &'static str = "/twirp/comments.Documented/";
+    /// The static descriptor of the service and its methods
+    pub const DESCRIPTOR: ::prost_twirp::ServiceDescriptor = ::prost_twirp::ServiceDescriptor {
+        package: "comments",
+        name: "Documented",
+        full_name: "comments.Documented",
+        methods: &[
+            ::prost_twirp::MethodDescriptor {
+                name: "leading",
+                proto_name: "Leading",
+                path: "/twirp/comments.Documented/Leading",
+                input_type: "comments.Doc",
+                output_type: "comments.Doc",
+            },
+            ::prost_twirp::MethodDescriptor {
+                name: "trailing",
+                proto_name: "Trailing",
+                path: "/twirp/comments.Documented/Trailing",
+                input_type: "comments.Doc",
+                output_type: "comments.Doc",
+            },
+            ::prost_twirp::MethodDescriptor {
+                name: "block",
+                proto_name: "Block",
+                path: "/twirp/comments.Documented/Block",
+                input_type: "comments.Doc",
+                output_type: "comments.Doc",
+            },
+            ::prost_twirp::MethodDescriptor {
+                name: "undocumented",
+                proto_name: "Undocumented",
+                path: "/twirp/comments.Documented/Undocumented",
+                input_type: "comments.Doc",
+                output_type: "comments.Doc",
+            },
+        ],
+    };
+    pub fn new_client(
+        client: ::hyper::client::Client<
+            ::hyper::client::HttpConnector,
+            ::hyper::body::Body,
+        >,
+        root_url: &str,
+    ) -> Box<dyn Documented> {
+        Box::new(DocumentedClient(::prost_twirp::HyperClient::new(client, root_url)))
+    }
+    pub fn new_server<T: Send + Sync + 'static + Documented>(
+        v: T,
+    ) -> Box<
+        dyn ::hyper::service::Service<
+            ::hyper::Request<::hyper::body::Body>,
+            Response = ::hyper::Response<::hyper::body::Body>,
+            Error = ::hyper::Error,
+            Future = ::std::pin::Pin<
+                Box<
+                    dyn ::std::future::Future<
+                        Output = ::std::result::Result<
+                            ::hyper::Response<::hyper::body::Body>,
+                            ::hyper::Error,
+                        >,
+                    > + Send,
+                >,
+            >,
+        > + Send,
+    > {
+        Box::new(
+            ::prost_twirp::HyperServer::new(DocumentedServer(::std::sync::Arc::new(v))),
+        )
+    }
+}
+pub struct DocumentedClient<C = ::hyper::client::HttpConnector>(
+    pub ::prost_twirp::HyperClient<C>,
+);
+impl<C: ::hyper::client::connect::Connect + Clone + Send + Sync + 'static> Documented
+for DocumentedClient<C> {
+    fn leading(&self, i: ::prost_twirp::PTReq<Doc>) -> ::prost_twirp::PTRes<Doc> {
+        self.0.go("/twirp/comments.Documented/Leading", i)
+    }
+    fn trailing(&self, i: ::prost_twirp::PTReq<Doc>) -> ::prost_twirp::PTRes<Doc> {
+        self.0.go("/twirp/comments.Documented/Trailing", i)
+    }
+    fn block(&self, i: ::prost_twirp::PTReq<Doc>) -> ::prost_twirp::PTRes<Doc> {
+        self.0.go("/twirp/comments.Documented/Block", i)
+    }
+    fn undocumented(&self, i: ::prost_twirp::PTReq<Doc>) -> ::prost_twirp::PTRes<Doc> {
+        self.0.go("/twirp/comments.Documented/Undocumented", i)
+    }
+}
+pub struct DocumentedServer<T: 'static + Documented>(pub ::std::sync::Arc<T>);
+impl<T: Send + Sync + 'static + Documented> ::prost_twirp::HyperService
+for DocumentedServer<T> {
+    fn handle(
+        &self,
+        req: ::prost_twirp::ServiceRequest<Vec<u8>>,
+    ) -> ::prost_twirp::PTRes<Vec<u8>> {
+        use ::futures::{FutureExt, TryFutureExt};
+        let static_service = self.0.clone();
+        match (req.method.clone(), req.uri.path()) {
+            (::hyper::Method::POST, "/twirp/comments.Documented/Leading") => {
+                Box::pin(
+                    ::std::future::ready(req.to_proto())
+                        .and_then(move |v| {
+                            static_service
+                                .leading(v)
+                                .map(|r| r.and_then(|v| v.to_proto_raw()))
+                        }),
+                )
+            }
+            (::hyper::Method::POST, "/twirp/comments.Documented/Trailing") => {
+                Box::pin(
+                    ::std::future::ready(req.to_proto())
+                        .and_then(move |v| {
+                            static_service
+                                .trailing(v)
+                                .map(|r| r.and_then(|v| v.to_proto_raw()))
+                        }),
+                )
+            }
+            (::hyper::Method::POST, "/twirp/comments.Documented/Block") => {
+                Box::pin(
+                    ::std::future::ready(req.to_proto())
+                        .and_then(move |v| {
+                            static_service
+                                .block(v)
+                                .map(|r| r.and_then(|v| v.to_proto_raw()))
+                        }),
+                )
+            }
+            (::hyper::Method::POST, "/twirp/comments.Documented/Undocumented") => {
+                Box::pin(
+                    ::std::future::ready(req.to_proto())
+                        .and_then(move |v| {
+                            static_service
+                                .undocumented(v)
+                                .map(|r| r.and_then(|v| v.to_proto_raw()))
+                        }),
+                )
+            }
+            (_, path) => {
+                Box::pin(
+                    ::std::future::ready(
+                        Err(
+                            ::prost_twirp::TwirpError::new(
+                                    ::hyper::StatusCode::NOT_FOUND,
+                                    "bad_route",
+                                    &format!("no handler for path {:?}", path),
+                                )
+                                .into(),
+                        ),
+                    ),
+                )
+            }
+        }
+    }
+    fn descriptor(&self) -> Option<&'static ::prost_twirp::ServiceDescriptor> {
+        Some(&<dyn Documented>::DESCRIPTOR)
+    }
+}
+
+pub type PTReq<I> = ::prost_twirp::PTReq<I>;
+pub type PTRes<O> = ::prost_twirp::PTRes<O>;
@@ -0,0 +1,180 @@
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Input {
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Output {
+}
+
+/// Method names that become Rust keywords once snake cased
+pub trait Keywords {
+    fn r#type(&self, i: ::prost_twirp::PTReq<Input>) -> ::prost_twirp::PTRes<Output>;
+    fn self_(&self, i: ::prost_twirp::PTReq<Input>) -> ::prost_twirp::PTRes<Output>;
+    fn r#async(&self, i: ::prost_twirp::PTReq<Input>) -> ::prost_twirp::PTRes<Output>;
+    fn r#match(&self, i: ::prost_twirp::PTReq<Input>) -> ::prost_twirp::PTRes<Output>;
+}
+#[allow(dead_code)]
+impl dyn Keywords {
+    /// The fully-qualified protobuf name of the service, without a leading dot
+    pub const FULLY_QUALIFIED_NAME: &'static str = "keywords.Keywords";
+    /// The prefix of every route of the service, including the trailing slash
+    pub const PATH_PREFIX: &'static str = "/twirp/keywords.Keywords/";
+    /// The static descriptor of the service and its methods
+    pub const DESCRIPTOR: ::prost_twirp::ServiceDescriptor = ::prost_twirp::ServiceDescriptor {
+        package: "keywords",
+        name: "Keywords",
+        full_name: "keywords.Keywords",
+        methods: &[
+            ::prost_twirp::MethodDescriptor {
+                name: "type",
+                proto_name: "Type",
+                path: "/twirp/keywords.Keywords/Type",
+                input_type: "keywords.Input",
+                output_type: "keywords.Output",
+            },
+            ::prost_twirp::MethodDescriptor {
+                name: "self_",
+                proto_name: "Self",
+                path: "/twirp/keywords.Keywords/Self",
+                input_type: "keywords.Input",
+                output_type: "keywords.Output",
+            },
+            ::prost_twirp::MethodDescriptor {
+                name: "async",
+                proto_name: "Async",
+                path: "/twirp/keywords.Keywords/Async",
+                input_type: "keywords.Input",
+                output_type: "keywords.Output",
+            },
+            ::prost_twirp::MethodDescriptor {
+                name: "match",
+                proto_name: "Match",
+                path: "/twirp/keywords.Keywords/Match",
+                input_type: "keywords.Input",
+                output_type: "keywords.Output",
+            },
+        ],
+    };
+    pub fn new_client(
+        client: ::hyper::client::Client<
+            ::hyper::client::HttpConnector,
+            ::hyper::body::Body,
+        >,
+        root_url: &str,
+    ) -> Box<dyn Keywords> {
+        Box::new(KeywordsClient(::prost_twirp::HyperClient::new(client, root_url)))
+    }
+    pub fn new_server<T: Send + Sync + 'static + Keywords>(
+        v: T,
+    ) -> Box<
+        dyn ::hyper::service::Service<
+            ::hyper::Request<::hyper::body::Body>,
+            Response = ::hyper::Response<::hyper::body::Body>,
+            Error = ::hyper::Error,
+            Future = ::std::pin::Pin<
+                Box<
+                    dyn ::std::future::Future<
+                        Output = ::std::result::Result<
+                            ::hyper::Response<::hyper::body::Body>,
+                            ::hyper::Error,
+                        >,
+                    > + Send,
+                >,
+            >,
+        > + Send,
+    > {
+        Box::new(
+            ::prost_twirp::HyperServer::new(KeywordsServer(::std::sync::Arc::new(v))),
+        )
+    }
+}
+pub struct KeywordsClient<C = ::hyper::client::HttpConnector>(
+    pub ::prost_twirp::HyperClient<C>,
+);
+impl<C: ::hyper::client::connect::Connect + Clone + Send + Sync + 'static> Keywords
+for KeywordsClient<C> {
+    fn r#type(&self, i: ::prost_twirp::PTReq<Input>) -> ::prost_twirp::PTRes<Output> {
+        self.0.go("/twirp/keywords.Keywords/Type", i)
+    }
+    fn self_(&self, i: ::prost_twirp::PTReq<Input>) -> ::prost_twirp::PTRes<Output> {
+        self.0.go("/twirp/keywords.Keywords/Self", i)
+    }
+    fn r#async(&self, i: ::prost_twirp::PTReq<Input>) -> ::prost_twirp::PTRes<Output> {
+        self.0.go("/twirp/keywords.Keywords/Async", i)
+    }
+    fn r#match(&self, i: ::prost_twirp::PTReq<Input>) -> ::prost_twirp::PTRes<Output> {
+        self.0.go("/twirp/keywords.Keywords/Match", i)
+    }
+}
+pub struct KeywordsServer<T: 'static + Keywords>(pub ::std::sync::Arc<T>);
+impl<T: Send + Sync + 'static + Keywords> ::prost_twirp::HyperService
+for KeywordsServer<T> {
+    fn handle(
+        &self,
+        req: ::prost_twirp::ServiceRequest<Vec<u8>>,
+    ) -> ::prost_twirp::PTRes<Vec<u8>> {
+        use ::futures::{FutureExt, TryFutureExt};
+        let static_service = self.0.clone();
+        match (req.method.clone(), req.uri.path()) {
+            (::hyper::Method::POST, "/twirp/keywords.Keywords/Type") => {
+                Box::pin(
+                    ::std::future::ready(req.to_proto())
+                        .and_then(move |v| {
+                            static_service
+                                .r#type(v)
+                                .map(|r| r.and_then(|v| v.to_proto_raw()))
+                        }),
+                )
+            }
+            (::hyper::Method::POST, "/twirp/keywords.Keywords/Self") => {
+                Box::pin(
+                    ::std::future::ready(req.to_proto())
+                        .and_then(move |v| {
+                            static_service
+                                .self_(v)
+                                .map(|r| r.and_then(|v| v.to_proto_raw()))
+                        }),
+                )
+            }
+            (::hyper::Method::POST, "/twirp/keywords.Keywords/Async") => {
+                Box::pin(
+                    ::std::future::ready(req.to_proto())
+                        .and_then(move |v| {
+                            static_service
+                                .r#async(v)
+                                .map(|r| r.and_then(|v| v.to_proto_raw()))
+                        }),
+                )
+            }
+            (::hyper::Method::POST, "/twirp/keywords.Keywords/Match") => {
+                Box::pin(
+                    ::std::future::ready(req.to_proto())
+                        .and_then(move |v| {
+                            static_service
+                                .r#match(v)
+                                .map(|r| r.and_then(|v| v.to_proto_raw()))
+                        }),
+                )
+            }
+            (_, path) => {
+                Box::pin(
+                    ::std::future::ready(
+                        Err(
+                            ::prost_twirp::TwirpError::new(
+                                    ::hyper::StatusCode::NOT_FOUND,
+                                    "bad_route",
+                                    &format!("no handler for path {:?}", path),
+                                )
+                                .into(),
+                        ),
+                    ),
+                )
+            }
+        }
+    }
+    fn descriptor(&self) -> Option<&'static ::prost_twirp::ServiceDescriptor> {
+        Some(&<dyn Keywords>::DESCRIPTOR)
+    }
+}
+
+pub type PTReq<I> = ::prost_twirp::PTReq<I>;
+pub type PTRes<O> = ::prost_twirp::PTRes<O>;
@@ -0,0 +1,350 @@
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Note {
+    #[prost(string, tag="1")]
+    pub payload: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Reply {
+    #[prost(string, tag="1")]
+    pub payload: ::prost::alloc::string::String,
+}
+
+pub trait Echo {
+    fn ping(&self, i: ::prost_twirp::PTReq<Note>) -> ::prost_twirp::PTRes<Reply>;
+    fn shout(&self, i: ::prost_twirp::PTReq<Note>) -> ::prost_twirp::PTRes<Reply>;
+}
+#[allow(dead_code)]
+impl dyn Echo {
+    /// The fully-qualified protobuf name of the service, without a leading dot
+    pub const FULLY_QUALIFIED_NAME: &'static str = "multi.alpha.Echo";
+    /// The prefix of every route of the service, including the trailing slash
+    pub const PATH_PREFIX: &'static str = "/twirp/multi.alpha.Echo/";
+    /// The static descriptor of the service and its methods
+    pub const DESCRIPTOR: ::prost_twirp::ServiceDescriptor = ::prost_twirp::ServiceDescriptor {
+        package: "multi.alpha",
+        name: "Echo",
+        full_name: "multi.alpha.Echo",
+        methods: &[
+            ::prost_twirp::MethodDescriptor {
+                name: "ping",
+                proto_name: "Ping",
+                path: "/twirp/multi.alpha.Echo/Ping",
+                input_type: "multi.alpha.Note",
+                output_type: "multi.alpha.Reply",
+            },
+            ::prost_twirp::MethodDescriptor {
+                name: "shout",
+                proto_name: "Shout",
+                path: "/twirp/multi.alpha.Echo/Shout",
+                input_type: "multi.alpha.Note",
+                output_type: "multi.alpha.Reply",
+            },
+        ],
+    };
+    pub fn new_client(
+        client: ::hyper::client::Client<
+            ::hyper::client::HttpConnector,
+            ::hyper::body::Body,
+        >,
+        root_url: &str,
+    ) -> Box<dyn Echo> {
+        Box::new(EchoClient(::prost_twirp::HyperClient::new(client, root_url)))
+    }
+    pub fn new_server<T: Send + Sync + 'static + Echo>(
+        v: T,
+    ) -> Box<
+        dyn ::hyper::service::Service<
+            ::hyper::Request<::hyper::body::Body>,
+            Response = ::hyper::Response<::hyper::body::Body>,
+            Error = ::hyper::Error,
+            Future = ::std::pin::Pin<
+                Box<
+                    dyn ::std::future::Future<
+                        Output = ::std::result::Result<
+                            ::hyper::Response<::hyper::body::Body>,
+                            ::hyper::Error,
+                        >,
+                    > + Send,
+                >,
+            >,
+        > + Send,
+    > {
+        Box::new(::prost_twirp::HyperServer::new(EchoServer(::std::sync::Arc::new(v))))
+    }
+}
+pub struct EchoClient<C = ::hyper::client::HttpConnector>(
+    pub ::prost_twirp::HyperClient<C>,
+);
+impl<C: ::hyper::client::connect::Connect + Clone + Send + Sync + 'static> Echo
+for EchoClient<C> {
+    fn ping(&self, i: ::prost_twirp::PTReq<Note>) -> ::prost_twirp::PTRes<Reply> {
+        self.0.go("/twirp/multi.alpha.Echo/Ping", i)
+    }
+    fn shout(&self, i: ::prost_twirp::PTReq<Note>) -> ::prost_twirp::PTRes<Reply> {
+        self.0.go("/twirp/multi.alpha.Echo/Shout", i)
+    }
+}
+pub struct EchoServer<T: 'static + Echo>(pub ::std::sync::Arc<T>);
+impl<T: Send + Sync + 'static + Echo> ::prost_twirp::HyperService for EchoServer<T> {
+    fn handle(
+        &self,
+        req: ::prost_twirp::ServiceRequest<Vec<u8>>,
+    ) -> ::prost_twirp::PTRes<Vec<u8>> {
+        use ::futures::{FutureExt, TryFutureExt};
+        let static_service = self.0.clone();
+        match (req.method.clone(), req.uri.path()) {
+            (::hyper::Method::POST, "/twirp/multi.alpha.Echo/Ping") => {
+                Box::pin(
+                    ::std::future::ready(req.to_proto())
+                        .and_then(move |v| {
+                            static_service
+                                .ping(v)
+                                .map(|r| r.and_then(|v| v.to_proto_raw()))
+                        }),
+                )
+            }
+            (::hyper::Method::POST, "/twirp/multi.alpha.Echo/Shout") => {
+                Box::pin(
+                    ::std::future::ready(req.to_proto())
+                        .and_then(move |v| {
+                            static_service
+                                .shout(v)
+                                .map(|r| r.and_then(|v| v.to_proto_raw()))
+                        }),
+                )
+            }
+            (_, path) => {
+                Box::pin(
+                    ::std::future::ready(
+                        Err(
+                            ::prost_twirp::TwirpError::new(
+                                    ::hyper::StatusCode::NOT_FOUND,
+                                    "bad_route",
+                                    &format!("no handler for path {:?}", path),
+                                )
+                                .into(),
+                        ),
+                    ),
+                )
+            }
+        }
+    }
+    fn descriptor(&self) -> Option<&'static ::prost_twirp::ServiceDescriptor> {
+        Some(&<dyn Echo>::DESCRIPTOR)
+    }
+}
+
+pub trait Counter {
+    fn count(&self, i: ::prost_twirp::PTReq<Note>) -> ::prost_twirp::PTRes<Reply>;
+}
+#[allow(dead_code)]
+impl dyn Counter {
+    /// The fully-qualified protobuf name of the service, without a leading dot
+    pub const FULLY_QUALIFIED_NAME: &'static str = "multi.alpha.Counter";
+    /// The prefix of every route of the service, including the trailing slash
+    pub const PATH_PREFIX: &'static str = "/twirp/multi.alpha.Counter/";
+    /// The static descriptor of the service and its methods
+    pub const DESCRIPTOR: ::prost_twirp::ServiceDescriptor = ::prost_twirp::ServiceDescriptor {
+        package: "multi.alpha",
+        name: "Counter",
+        full_name: "multi.alpha.Counter",
+        methods: &[
+            ::prost_twirp::MethodDescriptor {
+                name: "count",
+                proto_name: "Count",
+                path: "/twirp/multi.alpha.Counter/Count",
+                input_type: "multi.alpha.Note",
+                output_type: "multi.alpha.Reply",
+            },
+        ],
+    };
+    pub fn new_client(
+        client: ::hyper::client::Client<
+            ::hyper::client::HttpConnector,
+            ::hyper::body::Body,
+        >,
+        root_url: &str,
+    ) -> Box<dyn Counter> {
+        Box::new(CounterClient(::prost_twirp::HyperClient::new(client, root_url)))
+    }
+    pub fn new_server<T: Send + Sync + 'static + Counter>(
+        v: T,
+    ) -> Box<
+        dyn ::hyper::service::Service<
+            ::hyper::Request<::hyper::body::Body>,
+            Response = ::hyper::Response<::hyper::body::Body>,
+            Error = ::hyper::Error,
+            Future = ::std::pin::Pin<
+                Box<
+                    dyn ::std::future::Future<
+                        Output = ::std::result::Result<
+                            ::hyper::Response<::hyper::body::Body>,
+                            ::hyper::Error,
+                        >,
+                    > + Send,
+                >,
+            >,
+        > + Send,
+    > {
+        Box::new(
+            ::prost_twirp::HyperServer::new(CounterServer(::std::sync::Arc::new(v))),
+        )
+    }
+}
+pub struct CounterClient<C = ::hyper::client::HttpConnector>(
+    pub ::prost_twirp::HyperClient<C>,
+);
+impl<C: ::hyper::client::connect::Connect + Clone + Send + Sync + 'static> Counter
+for CounterClient<C> {
+    fn count(&self, i: ::prost_twirp::PTReq<Note>) -> ::prost_twirp::PTRes<Reply> {
+        self.0.go("/twirp/multi.alpha.Counter/Count", i)
+    }
+}
+pub struct CounterServer<T: 'static + Counter>(pub ::std::sync::Arc<T>);
+impl<T: Send + Sync + 'static + Counter> ::prost_twirp::HyperService
+for CounterServer<T> {
+    fn handle(
+        &self,
+        req: ::prost_twirp::ServiceRequest<Vec<u8>>,
+    ) -> ::prost_twirp::PTRes<Vec<u8>> {
+        use ::futures::{FutureExt, TryFutureExt};
+        let static_service = self.0.clone();
+        match (req.method.clone(), req.uri.path()) {
+            (::hyper::Method::POST, "/twirp/multi.alpha.Counter/Count") => {
+                Box::pin(
+                    ::std::future::ready(req.to_proto())
+                        .and_then(move |v| {
+                            static_service
+                                .count(v)
+                                .map(|r| r.and_then(|v| v.to_proto_raw()))
+                        }),
+                )
+            }
+            (_, path) => {
+                Box::pin(
+                    ::std::future::ready(
+                        Err(
+                            ::prost_twirp::TwirpError::new(
+                                    ::hyper::StatusCode::NOT_FOUND,
+                                    "bad_route",
+                                    &format!("no handler for path {:?}", path),
+                                )
+                                .into(),
+                        ),
+                    ),
+                )
+            }
+        }
+    }
+    fn descriptor(&self) -> Option<&'static ::prost_twirp::ServiceDescriptor> {
+        Some(&<dyn Counter>::DESCRIPTOR)
+    }
+}
+
+/// Second file in the same package, its services end up in the same output file
+pub trait Mirror {
+    fn reflect(&self, i: ::prost_twirp::PTReq<Note>) -> ::prost_twirp::PTRes<Reply>;
+}
+#[allow(dead_code)]
+impl dyn Mirror {
+    /// The fully-qualified protobuf name of the service, without a leading dot
+    pub const FULLY_QUALIFIED_NAME: &'static str = "multi.alpha.Mirror";
+    /// The prefix of every route of the service, including the trailing slash
+    pub const PATH_PREFIX: &'static str = "/twirp/multi.alpha.Mirror/";
+    /// The static descriptor of the service and its methods
+    pub const DESCRIPTOR: ::prost_twirp::ServiceDescriptor = ::prost_twirp::ServiceDescriptor {
+        package: "multi.alpha",
+        name: "Mirror",
+        full_name: "multi.alpha.Mirror",
+        methods: &[
+            ::prost_twirp::MethodDescriptor {
+                name: "reflect",
+                proto_name: "Reflect",
+                path: "/twirp/multi.alpha.Mirror/Reflect",
+                input_type: "multi.alpha.Note",
+                output_type: "multi.alpha.Reply",
+            },
+        ],
+    };
+    pub fn new_client(
+        client: ::hyper::client::Client<
+            ::hyper::client::HttpConnector,
+            ::hyper::body::Body,
+        >,
+        root_url: &str,
+    ) -> Box<dyn Mirror> {
+        Box::new(MirrorClient(::prost_twirp::HyperClient::new(client, root_url)))
+    }
+    pub fn new_server<T: Send + Sync + 'static + Mirror>(
+        v: T,
+    ) -> Box<
+        dyn ::hyper::service::Service<
+            ::hyper::Request<::hyper::body::Body>,
+            Response = ::hyper::Response<::hyper::body::Body>,
+            Error = ::hyper::Error,
+            Future = ::std::pin::Pin<
+                Box<
+                    dyn ::std::future::Future<
+                        Output = ::std::result::Result<
+                            ::hyper::Response<::hyper::body::Body>,
+                            ::hyper::Error,
+                        >,
+                    > + Send,
+                >,
+            >,
+        > + Send,
+    > {
+        Box::new(::prost_twirp::HyperServer::new(MirrorServer(::std::sync::Arc::new(v))))
+    }
+}
+pub struct MirrorClient<C = ::hyper::client::HttpConnector>(
+    pub ::prost_twirp::HyperClient<C>,
+);
+impl<C: ::hyper::client::connect::Connect + Clone + Send + Sync + 'static> Mirror
+for MirrorClient<C> {
+    fn reflect(&self, i: ::prost_twirp::PTReq<Note>) -> ::prost_twirp::PTRes<Reply> {
+        self.0.go("/twirp/multi.alpha.Mirror/Reflect", i)
+    }
+}
+pub struct MirrorServer<T: 'static + Mirror>(pub ::std::sync::Arc<T>);
+impl<T: Send + Sync + 'static + Mirror> ::prost_twirp::HyperService for MirrorServer<T> {
+    fn handle(
+        &self,
+        req: ::prost_twirp::ServiceRequest<Vec<u8>>,
+    ) -> ::prost_twirp::PTRes<Vec<u8>> {
+        use ::futures::{FutureExt, TryFutureExt};
+        let static_service = self.0.clone();
+        match (req.method.clone(), req.uri.path()) {
+            (::hyper::Method::POST, "/twirp/multi.alpha.Mirror/Reflect") => {
+                Box::pin(
+                    ::std::future::ready(req.to_proto())
+                        .and_then(move |v| {
+                            static_service
+                                .reflect(v)
+                                .map(|r| r.and_then(|v| v.to_proto_raw()))
+                        }),
+                )
+            }
+            (_, path) => {
+                Box::pin(
+                    ::std::future::ready(
+                        Err(
+                            ::prost_twirp::TwirpError::new(
+                                    ::hyper::StatusCode::NOT_FOUND,
+                                    "bad_route",
+                                    &format!("no handler for path {:?}", path),
+                                )
+                                .into(),
+                        ),
+                    ),
+                )
+            }
+        }
+    }
+    fn descriptor(&self) -> Option<&'static ::prost_twirp::ServiceDescriptor> {
+        Some(&<dyn Mirror>::DESCRIPTOR)
+    }
+}
+
+pub type PTReq<I> = ::prost_twirp::PTReq<I>;
+pub type PTRes<O> = ::prost_twirp::PTRes<O>;
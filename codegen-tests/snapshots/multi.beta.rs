@@ -0,0 +1,257 @@
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Relayed {
+    #[prost(message, optional, tag="1")]
+    pub reply: ::core::option::Option<super::alpha::Reply>,
+    #[prost(int32, tag="2")]
+    pub hops: i32,
+}
+
+pub trait Relay {
+    fn forward(
+        &self,
+        i: ::prost_twirp::PTReq<super::alpha::Note>,
+    ) -> ::prost_twirp::PTRes<Relayed>;
+    fn back(
+        &self,
+        i: ::prost_twirp::PTReq<Relayed>,
+    ) -> ::prost_twirp::PTRes<super::alpha::Reply>;
+}
+#[allow(dead_code)]
+impl dyn Relay {
+    /// The fully-qualified protobuf name of the service, without a leading dot
+    pub const FULLY_QUALIFIED_NAME: &'static str = "multi.beta.Relay";
+    /// The prefix of every route of the service, including the trailing slash
+    pub const PATH_PREFIX: &'static str = "/twirp/multi.beta.Relay/";
+    /// The static descriptor of the service and its methods
+    pub const DESCRIPTOR: ::prost_twirp::ServiceDescriptor = ::prost_twirp::ServiceDescriptor {
+        package: "multi.beta",
+        name: "Relay",
+        full_name: "multi.beta.Relay",
+        methods: &[
+            ::prost_twirp::MethodDescriptor {
+                name: "forward",
+                proto_name: "Forward",
+                path: "/twirp/multi.beta.Relay/Forward",
+                input_type: "multi.alpha.Note",
+                output_type: "multi.beta.Relayed",
+            },
+            ::prost_twirp::MethodDescriptor {
+                name: "back",
+                proto_name: "Back",
+                path: "/twirp/multi.beta.Relay/Back",
+                input_type: "multi.beta.Relayed",
+                output_type: "multi.alpha.Reply",
+            },
+        ],
+    };
+    pub fn new_client(
+        client: ::hyper::client::Client<
+            ::hyper::client::HttpConnector,
+            ::hyper::body::Body,
+        >,
+        root_url: &str,
+    ) -> Box<dyn Relay> {
+        Box::new(RelayClient(::prost_twirp::HyperClient::new(client, root_url)))
+    }
+    pub fn new_server<T: Send + Sync + 'static + Relay>(
+        v: T,
+    ) -> Box<
+        dyn ::hyper::service::Service<
+            ::hyper::Request<::hyper::body::Body>,
+            Response = ::hyper::Response<::hyper::body::Body>,
+            Error = ::hyper::Error,
+            Future = ::std::pin::Pin<
+                Box<
+                    dyn ::std::future::Future<
+                        Output = ::std::result::Result<
+                            ::hyper::Response<::hyper::body::Body>,
+                            ::hyper::Error,
+                        >,
+                    > + Send,
+                >,
+            >,
+        > + Send,
+    > {
+        Box::new(::prost_twirp::HyperServer::new(RelayServer(::std::sync::Arc::new(v))))
+    }
+}
+pub struct RelayClient<C = ::hyper::client::HttpConnector>(
+    pub ::prost_twirp::HyperClient<C>,
+);
+impl<C: ::hyper::client::connect::Connect + Clone + Send + Sync + 'static> Relay
+for RelayClient<C> {
+    fn forward(
+        &self,
+        i: ::prost_twirp::PTReq<super::alpha::Note>,
+    ) -> ::prost_twirp::PTRes<Relayed> {
+        self.0.go("/twirp/multi.beta.Relay/Forward", i)
+    }
+    fn back(
+        &self,
+        i: ::prost_twirp::PTReq<Relayed>,
+    ) -> ::prost_twirp::PTRes<super::alpha::Reply> {
+        self.0.go("/twirp/multi.beta.Relay/Back", i)
+    }
+}
+pub struct RelayServer<T: 'static + Relay>(pub ::std::sync::Arc<T>);
+impl<T: Send + Sync + 'static + Relay> ::prost_twirp::HyperService for RelayServer<T> {
+    fn handle(
+        &self,
+        req: ::prost_twirp::ServiceRequest<Vec<u8>>,
+    ) -> ::prost_twirp::PTRes<Vec<u8>> {
+        use ::futures::{FutureExt, TryFutureExt};
+        let static_service = self.0.clone();
+        match (req.method.clone(), req.uri.path()) {
+            (::hyper::Method::POST, "/twirp/multi.beta.Relay/Forward") => {
+                Box::pin(
+                    ::std::future::ready(req.to_proto())
+                        .and_then(move |v| {
+                            static_service
+                                .forward(v)
+                                .map(|r| r.and_then(|v| v.to_proto_raw()))
+                        }),
+                )
+            }
+            (::hyper::Method::POST, "/twirp/multi.beta.Relay/Back") => {
+                Box::pin(
+                    ::std::future::ready(req.to_proto())
+                        .and_then(move |v| {
+                            static_service
+                                .back(v)
+                                .map(|r| r.and_then(|v| v.to_proto_raw()))
+                        }),
+                )
+            }
+            (_, path) => {
+                Box::pin(
+                    ::std::future::ready(
+                        Err(
+                            ::prost_twirp::TwirpError::new(
+                                    ::hyper::StatusCode::NOT_FOUND,
+                                    "bad_route",
+                                    &format!("no handler for path {:?}", path),
+                                )
+                                .into(),
+                        ),
+                    ),
+                )
+            }
+        }
+    }
+    fn descriptor(&self) -> Option<&'static ::prost_twirp::ServiceDescriptor> {
+        Some(&<dyn Relay>::DESCRIPTOR)
+    }
+}
+
+pub trait Status {
+    fn check(
+        &self,
+        i: ::prost_twirp::PTReq<super::alpha::Note>,
+    ) -> ::prost_twirp::PTRes<super::alpha::Reply>;
+}
+#[allow(dead_code)]
+impl dyn Status {
+    /// The fully-qualified protobuf name of the service, without a leading dot
+    pub const FULLY_QUALIFIED_NAME: &'static str = "multi.beta.Status";
+    /// The prefix of every route of the service, including the trailing slash
+    pub const PATH_PREFIX: &'static str = "/twirp/multi.beta.Status/";
+    /// The static descriptor of the service and its methods
+    pub const DESCRIPTOR: ::prost_twirp::ServiceDescriptor = ::prost_twirp::ServiceDescriptor {
+        package: "multi.beta",
+        name: "Status",
+        full_name: "multi.beta.Status",
+        methods: &[
+            ::prost_twirp::MethodDescriptor {
+                name: "check",
+                proto_name: "Check",
+                path: "/twirp/multi.beta.Status/Check",
+                input_type: "multi.alpha.Note",
+                output_type: "multi.alpha.Reply",
+            },
+        ],
+    };
+    pub fn new_client(
+        client: ::hyper::client::Client<
+            ::hyper::client::HttpConnector,
+            ::hyper::body::Body,
+        >,
+        root_url: &str,
+    ) -> Box<dyn Status> {
+        Box::new(StatusClient(::prost_twirp::HyperClient::new(client, root_url)))
+    }
+    pub fn new_server<T: Send + Sync + 'static + Status>(
+        v: T,
+    ) -> Box<
+        dyn ::hyper::service::Service<
+            ::hyper::Request<::hyper::body::Body>,
+            Response = ::hyper::Response<::hyper::body::Body>,
+            Error = ::hyper::Error,
+            Future = ::std::pin::Pin<
+                Box<
+                    dyn ::std::future::Future<
+                        Output = ::std::result::Result<
+                            ::hyper::Response<::hyper::body::Body>,
+                            ::hyper::Error,
+                        >,
+                    > + Send,
+                >,
+            >,
+        > + Send,
+    > {
+        Box::new(::prost_twirp::HyperServer::new(StatusServer(::std::sync::Arc::new(v))))
+    }
+}
+pub struct StatusClient<C = ::hyper::client::HttpConnector>(
+    pub ::prost_twirp::HyperClient<C>,
+);
+impl<C: ::hyper::client::connect::Connect + Clone + Send + Sync + 'static> Status
+for StatusClient<C> {
+    fn check(
+        &self,
+        i: ::prost_twirp::PTReq<super::alpha::Note>,
+    ) -> ::prost_twirp::PTRes<super::alpha::Reply> {
+        self.0.go("/twirp/multi.beta.Status/Check", i)
+    }
+}
+pub struct StatusServer<T: 'static + Status>(pub ::std::sync::Arc<T>);
+impl<T: Send + Sync + 'static + Status> ::prost_twirp::HyperService for StatusServer<T> {
+    fn handle(
+        &self,
+        req: ::prost_twirp::ServiceRequest<Vec<u8>>,
+    ) -> ::prost_twirp::PTRes<Vec<u8>> {
+        use ::futures::{FutureExt, TryFutureExt};
+        let static_service = self.0.clone();
+        match (req.method.clone(), req.uri.path()) {
+            (::hyper::Method::POST, "/twirp/multi.beta.Status/Check") => {
+                Box::pin(
+                    ::std::future::ready(req.to_proto())
+                        .and_then(move |v| {
+                            static_service
+                                .check(v)
+                                .map(|r| r.and_then(|v| v.to_proto_raw()))
+                        }),
+                )
+            }
+            (_, path) => {
+                Box::pin(
+                    ::std::future::ready(
+                        Err(
+                            ::prost_twirp::TwirpError::new(
+                                    ::hyper::StatusCode::NOT_FOUND,
+                                    "bad_route",
+                                    &format!("no handler for path {:?}", path),
+                                )
+                                .into(),
+                        ),
+                    ),
+                )
+            }
+        }
+    }
+    fn descriptor(&self) -> Option<&'static ::prost_twirp::ServiceDescriptor> {
+        Some(&<dyn Status>::DESCRIPTOR)
+    }
+}
+
+pub type PTReq<I> = ::prost_twirp::PTReq<I>;
+pub type PTRes<O> = ::prost_twirp::PTRes<O>;
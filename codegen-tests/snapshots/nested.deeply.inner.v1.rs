@@ -0,0 +1,133 @@
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Item {
+    #[prost(string, tag="1")]
+    pub id: ::prost::alloc::string::String,
+}
+
+pub trait Store {
+    fn put(&self, i: ::prost_twirp::PTReq<Item>) -> ::prost_twirp::PTRes<Item>;
+    fn get(&self, i: ::prost_twirp::PTReq<Item>) -> ::prost_twirp::PTRes<Item>;
+}
+#[allow(dead_code)]
+impl dyn Store {
+    /// The fully-qualified protobuf name of the service, without a leading dot
+    pub const FULLY_QUALIFIED_NAME: &'static str = "nested.deeply.inner.v1.Store";
+    /// The prefix of every route of the service, including the trailing slash
+    pub const PATH_PREFIX: &'static str = "/twirp/nested.deeply.inner.v1.Store/";
+    /// The static descriptor of the service and its methods
+    pub const DESCRIPTOR: ::prost_twirp::ServiceDescriptor = ::prost_twirp::ServiceDescriptor {
+        package: "nested.deeply.inner.v1",
+        name: "Store",
+        full_name: "nested.deeply.inner.v1.Store",
+        methods: &[
+            ::prost_twirp::MethodDescriptor {
+                name: "put",
+                proto_name: "Put",
+                path: "/twirp/nested.deeply.inner.v1.Store/Put",
+                input_type: "nested.deeply.inner.v1.Item",
+                output_type: "nested.deeply.inner.v1.Item",
+            },
+            ::prost_twirp::MethodDescriptor {
+                name: "get",
+                proto_name: "Get",
+                path: "/twirp/nested.deeply.inner.v1.Store/Get",
+                input_type: "nested.deeply.inner.v1.Item",
+                output_type: "nested.deeply.inner.v1.Item",
+            },
+        ],
+    };
+    pub fn new_client(
+        client: ::hyper::client::Client<
+            ::hyper::client::HttpConnector,
+            ::hyper::body::Body,
+        >,
+        root_url: &str,
+    ) -> Box<dyn Store> {
+        Box::new(StoreClient(::prost_twirp::HyperClient::new(client, root_url)))
+    }
+    pub fn new_server<T: Send + Sync + 'static + Store>(
+        v: T,
+    ) -> Box<
+        dyn ::hyper::service::Service<
+            ::hyper::Request<::hyper::body::Body>,
+            Response = ::hyper::Response<::hyper::body::Body>,
+            Error = ::hyper::Error,
+            Future = ::std::pin::Pin<
+                Box<
+                    dyn ::std::future::Future<
+                        Output = ::std::result::Result<
+                            ::hyper::Response<::hyper::body::Body>,
+                            ::hyper::Error,
+                        >,
+                    > + Send,
+                >,
+            >,
+        > + Send,
+    > {
+        Box::new(::prost_twirp::HyperServer::new(StoreServer(::std::sync::Arc::new(v))))
+    }
+}
+pub struct StoreClient<C = ::hyper::client::HttpConnector>(
+    pub ::prost_twirp::HyperClient<C>,
+);
+impl<C: ::hyper::client::connect::Connect + Clone + Send + Sync + 'static> Store
+for StoreClient<C> {
+    fn put(&self, i: ::prost_twirp::PTReq<Item>) -> ::prost_twirp::PTRes<Item> {
+        self.0.go("/twirp/nested.deeply.inner.v1.Store/Put", i)
+    }
+    fn get(&self, i: ::prost_twirp::PTReq<Item>) -> ::prost_twirp::PTRes<Item> {
+        self.0.go("/twirp/nested.deeply.inner.v1.Store/Get", i)
+    }
+}
+pub struct StoreServer<T: 'static + Store>(pub ::std::sync::Arc<T>);
+impl<T: Send + Sync + 'static + Store> ::prost_twirp::HyperService for StoreServer<T> {
+    fn handle(
+        &self,
+        req: ::prost_twirp::ServiceRequest<Vec<u8>>,
+    ) -> ::prost_twirp::PTRes<Vec<u8>> {
+        use ::futures::{FutureExt, TryFutureExt};
+        let static_service = self.0.clone();
+        match (req.method.clone(), req.uri.path()) {
+            (::hyper::Method::POST, "/twirp/nested.deeply.inner.v1.Store/Put") => {
+                Box::pin(
+                    ::std::future::ready(req.to_proto())
+                        .and_then(move |v| {
+                            static_service
+                                .put(v)
+                                .map(|r| r.and_then(|v| v.to_proto_raw()))
+                        }),
+                )
+            }
+            (::hyper::Method::POST, "/twirp/nested.deeply.inner.v1.Store/Get") => {
+                Box::pin(
+                    ::std::future::ready(req.to_proto())
+                        .and_then(move |v| {
+                            static_service
+                                .get(v)
+                                .map(|r| r.and_then(|v| v.to_proto_raw()))
+                        }),
+                )
+            }
+            (_, path) => {
+                Box::pin(
+                    ::std::future::ready(
+                        Err(
+                            ::prost_twirp::TwirpError::new(
+                                    ::hyper::StatusCode::NOT_FOUND,
+                                    "bad_route",
+                                    &format!("no handler for path {:?}", path),
+                                )
+                                .into(),
+                        ),
+                    ),
+                )
+            }
+        }
+    }
+    fn descriptor(&self) -> Option<&'static ::prost_twirp::ServiceDescriptor> {
+        Some(&<dyn Store>::DESCRIPTOR)
+    }
+}
+
+pub type PTReq<I> = ::prost_twirp::PTReq<I>;
+pub type PTRes<O> = ::prost_twirp::PTRes<O>;
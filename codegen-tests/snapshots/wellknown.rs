@@ -0,0 +1,194 @@
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Event {
+    #[prost(message, optional, tag="1")]
+    pub at: ::core::option::Option<::prost_types::Timestamp>,
+}
+
+/// Inputs and outputs that prost maps to types outside of this package
+pub trait Clock {
+    fn now(
+        &self,
+        i: ::prost_twirp::PTReq<()>,
+    ) -> ::prost_twirp::PTRes<::prost_types::Timestamp>;
+    fn schedule(
+        &self,
+        i: ::prost_twirp::PTReq<::prost_types::Timestamp>,
+    ) -> ::prost_twirp::PTRes<Event>;
+    fn describe(
+        &self,
+        i: ::prost_twirp::PTReq<::prost::alloc::string::String>,
+    ) -> ::prost_twirp::PTRes<::prost::alloc::string::String>;
+    fn reset(&self, i: ::prost_twirp::PTReq<()>) -> ::prost_twirp::PTRes<()>;
+}
+#[allow(dead_code)]
+impl dyn Clock {
+    /// The fully-qualified protobuf name of the service, without a leading dot
+    pub const FULLY_QUALIFIED_NAME: &'static str = "wellknown.Clock";
+    /// The prefix of every route of the service, including the trailing slash
+    pub const PATH_PREFIX: &'static str = "/twirp/wellknown.Clock/";
+    /// The static descriptor of the service and its methods
+    pub const DESCRIPTOR: ::prost_twirp::ServiceDescriptor = ::prost_twirp::ServiceDescriptor {
+        package: "wellknown",
+        name: "Clock",
+        full_name: "wellknown.Clock",
+        methods: &[
+            ::prost_twirp::MethodDescriptor {
+                name: "now",
+                proto_name: "Now",
+                path: "/twirp/wellknown.Clock/Now",
+                input_type: "google.protobuf.Empty",
+                output_type: "google.protobuf.Timestamp",
+            },
+            ::prost_twirp::MethodDescriptor {
+                name: "schedule",
+                proto_name: "Schedule",
+                path: "/twirp/wellknown.Clock/Schedule",
+                input_type: "google.protobuf.Timestamp",
+                output_type: "wellknown.Event",
+            },
+            ::prost_twirp::MethodDescriptor {
+                name: "describe",
+                proto_name: "Describe",
+                path: "/twirp/wellknown.Clock/Describe",
+                input_type: "google.protobuf.StringValue",
+                output_type: "google.protobuf.StringValue",
+            },
+            ::prost_twirp::MethodDescriptor {
+                name: "reset",
+                proto_name: "Reset",
+                path: "/twirp/wellknown.Clock/Reset",
+                input_type: "google.protobuf.Empty",
+                output_type: "google.protobuf.Empty",
+            },
+        ],
+    };
+    pub fn new_client(
+        client: ::hyper::client::Client<
+            ::hyper::client::HttpConnector,
+            ::hyper::body::Body,
+        >,
+        root_url: &str,
+    ) -> Box<dyn Clock> {
+        Box::new(ClockClient(::prost_twirp::HyperClient::new(client, root_url)))
+    }
+    pub fn new_server<T: Send + Sync + 'static + Clock>(
+        v: T,
+    ) -> Box<
+        dyn ::hyper::service::Service<
+            ::hyper::Request<::hyper::body::Body>,
+            Response = ::hyper::Response<::hyper::body::Body>,
+            Error = ::hyper::Error,
+            Future = ::std::pin::Pin<
+                Box<
+                    dyn ::std::future::Future<
+                        Output = ::std::result::Result<
+                            ::hyper::Response<::hyper::body::Body>,
+                            ::hyper::Error,
+                        >,
+                    > + Send,
+                >,
+            >,
+        > + Send,
+    > {
+        Box::new(::prost_twirp::HyperServer::new(ClockServer(::std::sync::Arc::new(v))))
+    }
+}
+pub struct ClockClient<C = ::hyper::client::HttpConnector>(
+    pub ::prost_twirp::HyperClient<C>,
+);
+impl<C: ::hyper::client::connect::Connect + Clone + Send + Sync + 'static> Clock
+for ClockClient<C> {
+    fn now(
+        &self,
+        i: ::prost_twirp::PTReq<()>,
+    ) -> ::prost_twirp::PTRes<::prost_types::Timestamp> {
+        self.0.go("/twirp/wellknown.Clock/Now", i)
+    }
+    fn schedule(
+        &self,
+        i: ::prost_twirp::PTReq<::prost_types::Timestamp>,
+    ) -> ::prost_twirp::PTRes<Event> {
+        self.0.go("/twirp/wellknown.Clock/Schedule", i)
+    }
+    fn describe(
+        &self,
+        i: ::prost_twirp::PTReq<::prost::alloc::string::String>,
+    ) -> ::prost_twirp::PTRes<::prost::alloc::string::String> {
+        self.0.go("/twirp/wellknown.Clock/Describe", i)
+    }
+    fn reset(&self, i: ::prost_twirp::PTReq<()>) -> ::prost_twirp::PTRes<()> {
+        self.0.go("/twirp/wellknown.Clock/Reset", i)
+    }
+}
+pub struct ClockServer<T: 'static + Clock>(pub ::std::sync::Arc<T>);
+impl<T: Send + Sync + 'static + Clock> ::prost_twirp::HyperService for ClockServer<T> {
+    fn handle(
+        &self,
+        req: ::prost_twirp::ServiceRequest<Vec<u8>>,
+    ) -> ::prost_twirp::PTRes<Vec<u8>> {
+        use ::futures::{FutureExt, TryFutureExt};
+        let static_service = self.0.clone();
+        match (req.method.clone(), req.uri.path()) {
+            (::hyper::Method::POST, "/twirp/wellknown.Clock/Now") => {
+                Box::pin(
+                    ::std::future::ready(req.to_proto())
+                        .and_then(move |v| {
+                            static_service
+                                .now(v)
+                                .map(|r| r.and_then(|v| v.to_proto_raw()))
+                        }),
+                )
+            }
+            (::hyper::Method::POST, "/twirp/wellknown.Clock/Schedule") => {
+                Box::pin(
+                    ::std::future::ready(req.to_proto())
+                        .and_then(move |v| {
+                            static_service
+                                .schedule(v)
+                                .map(|r| r.and_then(|v| v.to_proto_raw()))
+                        }),
+                )
+            }
+            (::hyper::Method::POST, "/twirp/wellknown.Clock/Describe") => {
+                Box::pin(
+                    ::std::future::ready(req.to_proto())
+                        .and_then(move |v| {
+                            static_service
+                                .describe(v)
+                                .map(|r| r.and_then(|v| v.to_proto_raw()))
+                        }),
+                )
+            }
+            (::hyper::Method::POST, "/twirp/wellknown.Clock/Reset") => {
+                Box::pin(
+                    ::std::future::ready(req.to_proto())
+                        .and_then(move |v| {
+                            static_service
+                                .reset(v)
+                                .map(|r| r.and_then(|v| v.to_proto_raw()))
+                        }),
+                )
+            }
+            (_, path) => {
+                Box::pin(
+                    ::std::future::ready(
+                        Err(
+                            ::prost_twirp::TwirpError::new(
+                                    ::hyper::StatusCode::NOT_FOUND,
+                                    "bad_route",
+                                    &format!("no handler for path {:?}", path),
+                                )
+                                .into(),
+                        ),
+                    ),
+                )
+            }
+        }
+    }
+    fn descriptor(&self) -> Option<&'static ::prost_twirp::ServiceDescriptor> {
+        Some(&<dyn Clock>::DESCRIPTOR)
+    }
+}
+
+pub type PTReq<I> = ::prost_twirp::PTReq<I>;
+pub type PTRes<O> = ::prost_twirp::PTRes<O>;
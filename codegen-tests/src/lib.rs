@@ -0,0 +1,70 @@
+//! Compiles the output of `TwirpServiceGenerator` for the protos in `protos/`.
+//!
+//! Every variant (with the runtime as a dependency, embedded, and with tonic adapters) is generated by `build.rs` into
+//! its own directory and included here so that any code generation regression fails the build. The generated files of
+//! the `runtime` variant are also snapshotted, see `tests/snapshots.rs`.
+//!
+//! To cover a new case, add a proto to `protos/` and to `PROTOS` in `build.rs`, include its package module below, then
+//! run `UPDATE_SNAPSHOTS=1 cargo test -p codegen-tests` and review the new snapshot.
+
+macro_rules! corpus {
+    ($variant:literal) => {
+        pub mod comments {
+            include!(concat!(env!("OUT_DIR"), "/", $variant, "/comments.rs"));
+        }
+        pub mod keywords {
+            include!(concat!(env!("OUT_DIR"), "/", $variant, "/keywords.rs"));
+        }
+        pub mod wellknown {
+            include!(concat!(env!("OUT_DIR"), "/", $variant, "/wellknown.rs"));
+        }
+        pub mod nested {
+            pub mod deeply {
+                pub mod inner {
+                    pub mod v1 {
+                        include!(concat!(env!("OUT_DIR"), "/", $variant, "/nested.deeply.inner.v1.rs"));
+                    }
+                }
+            }
+        }
+        pub mod multi {
+            pub mod alpha {
+                include!(concat!(env!("OUT_DIR"), "/", $variant, "/multi.alpha.rs"));
+            }
+            pub mod beta {
+                include!(concat!(env!("OUT_DIR"), "/", $variant, "/multi.beta.rs"));
+            }
+        }
+
+        pub mod nopackage {
+            include!(concat!(env!("OUT_DIR"), "/", $variant, "/_.rs"));
+        }
+
+        /// Every package module must carry its own aliases, regardless of how many packages were compiled at once
+        #[allow(dead_code)]
+        mod aliases {
+            use super::multi::{alpha, beta};
+
+            fn alpha_aliases(_: alpha::PTReq<alpha::Note>) -> Option<alpha::PTRes<alpha::Reply>> { None }
+            fn beta_aliases(_: beta::PTReq<alpha::Note>) -> Option<beta::PTRes<beta::Relayed>> { None }
+        }
+    };
+}
+
+// The generated code deliberately spells out the full hyper service type and passes `ProstTwirpError` around by value
+#[allow(clippy::type_complexity, clippy::result_large_err)]
+pub mod runtime {
+    corpus!("runtime");
+}
+
+// tonic's generated code has its own style
+#[allow(clippy::type_complexity, clippy::result_large_err, clippy::all)]
+pub mod tonic_adapters {
+    corpus!("tonic");
+}
+
+// The embedded runtime is linted as part of the prost-twirp crate itself
+#[allow(clippy::all)]
+pub mod embedded {
+    corpus!("embedded");
+}
@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use futures::future;
+use prost_twirp::{HyperService, MethodDescriptor, ServiceDescriptor};
+
+use codegen_tests::runtime::keywords;
+use codegen_tests::runtime::multi::{alpha, beta};
+
+struct EchoService;
+impl alpha::Echo for EchoService {
+    fn ping(&self, i: alpha::PTReq<alpha::Note>) -> alpha::PTRes<alpha::Reply> {
+        Box::pin(future::ok(alpha::Reply { payload: i.input.payload }.into()))
+    }
+
+    fn shout(&self, i: alpha::PTReq<alpha::Note>) -> alpha::PTRes<alpha::Reply> {
+        Box::pin(future::ok(alpha::Reply { payload: i.input.payload.to_uppercase() }.into()))
+    }
+}
+
+#[test]
+fn descriptor_lists_methods() {
+    assert_eq!(<dyn alpha::Echo>::DESCRIPTOR, ServiceDescriptor {
+        package: "multi.alpha",
+        name: "Echo",
+        full_name: "multi.alpha.Echo",
+        methods: &[
+            MethodDescriptor {
+                name: "ping",
+                proto_name: "Ping",
+                path: "/twirp/multi.alpha.Echo/Ping",
+                input_type: "multi.alpha.Note",
+                output_type: "multi.alpha.Reply",
+            },
+            MethodDescriptor {
+                name: "shout",
+                proto_name: "Shout",
+                path: "/twirp/multi.alpha.Echo/Shout",
+                input_type: "multi.alpha.Note",
+                output_type: "multi.alpha.Reply",
+            },
+        ],
+    });
+}
+
+#[test]
+fn descriptor_uses_cross_package_types() {
+    let forward = <dyn beta::Relay>::DESCRIPTOR.method("Forward").unwrap();
+    assert_eq!(forward.input_type, "multi.alpha.Note");
+    assert_eq!(forward.output_type, "multi.beta.Relayed");
+    assert!(<dyn beta::Relay>::DESCRIPTOR.method("Ping").is_none());
+}
+
+#[test]
+fn descriptor_finds_method_by_path() {
+    let descriptor = &<dyn alpha::Echo>::DESCRIPTOR;
+    assert_eq!(descriptor.method_for_path("/twirp/multi.alpha.Echo/Shout").map(|m| m.name), Some("shout"));
+    assert!(descriptor.method_for_path("/twirp/multi.alpha.Echo/Whisper").is_none());
+}
+
+#[test]
+fn descriptor_names_keyword_methods_without_raw_prefix() {
+    let names: Vec<_> = <dyn keywords::Keywords>::DESCRIPTOR.methods.iter().map(|m| (m.name, m.proto_name)).collect();
+    assert_eq!(names, vec![("type", "Type"), ("self_", "Self"), ("async", "Async"), ("match", "Match")]);
+}
+
+#[test]
+fn server_exposes_descriptor() {
+    let server = alpha::EchoServer(Arc::new(EchoService));
+    assert_eq!(server.descriptor(), Some(&<dyn alpha::Echo>::DESCRIPTOR));
+}
@@ -0,0 +1,43 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+use futures::future;
+use hyper::{Client, Server};
+use hyper::service::make_service_fn;
+
+use codegen_tests::runtime::{multi, nopackage};
+
+struct GreeterService;
+impl nopackage::Greeter for GreeterService {
+    fn hello(&self, _i: nopackage::PTReq<()>) -> nopackage::PTRes<()> {
+        Box::pin(future::ok(().into()))
+    }
+}
+
+#[test]
+fn full_names_skip_empty_package() {
+    assert_eq!(<dyn nopackage::Greeter>::FULLY_QUALIFIED_NAME, "Greeter");
+    assert_eq!(<dyn nopackage::Greeter>::PATH_PREFIX, "/twirp/Greeter/");
+    assert_eq!(<dyn multi::alpha::Echo>::FULLY_QUALIFIED_NAME, "multi.alpha.Echo");
+    assert_eq!(<dyn multi::beta::Relay>::PATH_PREFIX, "/twirp/multi.beta.Relay/");
+}
+
+#[tokio::test]
+async fn empty_package_round_trip() {
+    let make_service = make_service_fn(|_conn| async {
+        Ok::<_, Infallible>(<dyn nopackage::Greeter>::new_server(GreeterService))
+    });
+    let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+    let root_url = format!("http://{}", server.local_addr());
+    tokio::spawn(server);
+
+    let client = <dyn nopackage::Greeter>::new_client(Client::new(), &root_url);
+    let resp = client.hello(().into()).await.unwrap();
+    assert_eq!(resp.status, hyper::StatusCode::OK);
+
+    // The route is the bare service name, not `/twirp/.Greeter/Hello`
+    let raw = Client::new().request(hyper::Request::post(format!("{}/twirp/Greeter/Hello", root_url))
+        .header(hyper::header::CONTENT_TYPE, "application/protobuf")
+        .body(hyper::Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(raw.status(), hyper::StatusCode::OK);
+}
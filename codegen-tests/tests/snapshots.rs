@@ -0,0 +1,63 @@
+//! Compares the generated code against the checked in snapshots.
+//!
+//! Run with `UPDATE_SNAPSHOTS=1` to overwrite the snapshots with the current output, then review the diff.
+
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+fn file_names(dir: &Path) -> BTreeSet<String> {
+    fs::read_dir(dir).map(|entries| {
+        entries.map(|entry| entry.unwrap().file_name().into_string().unwrap()).collect()
+    }).unwrap_or_default()
+}
+
+#[test]
+fn generated_code_matches_snapshots() {
+    let generated_dir = Path::new(env!("OUT_DIR")).join("runtime");
+    let snapshot_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("snapshots");
+    let update = env::var_os("UPDATE_SNAPSHOTS").is_some();
+    let generated = file_names(&generated_dir);
+    let snapshots = file_names(&snapshot_dir);
+
+    if update {
+        fs::create_dir_all(&snapshot_dir).unwrap();
+        for stale in snapshots.difference(&generated) {
+            fs::remove_file(snapshot_dir.join(stale)).unwrap();
+        }
+        for name in &generated {
+            fs::copy(generated_dir.join(name), snapshot_dir.join(name)).unwrap();
+        }
+        return;
+    }
+
+    assert_eq!(generated, snapshots, "generated files differ from snapshots, rerun with UPDATE_SNAPSHOTS=1");
+    let mismatched: Vec<_> = generated.iter().filter_map(|name| {
+        let actual = fs::read_to_string(generated_dir.join(name)).unwrap();
+        let expected = fs::read_to_string(snapshot_dir.join(name)).unwrap();
+        first_difference(&expected, &actual).map(|line| format!("{} (line {})", name, line))
+    }).collect();
+    assert!(mismatched.is_empty(), "generated code differs from snapshots in {:?}, rerun with UPDATE_SNAPSHOTS=1",
+        mismatched);
+}
+
+/// The 1-based number of the first line that differs, if any
+fn first_difference(expected: &str, actual: &str) -> Option<usize> {
+    if expected == actual { return None; }
+    // Unlike `lines`, splitting keeps trailing newlines and `\r`s, so different texts always have a different line
+    let expected_lines: Vec<_> = expected.split('\n').collect();
+    let actual_lines: Vec<_> = actual.split('\n').collect();
+    let same = expected_lines.iter().zip(&actual_lines).take_while(|(expected, actual)| expected == actual).count();
+    // Past the lines both have in common, the longer one has an extra line
+    Some(same + 1)
+}
+
+#[test]
+fn first_difference_finds_whitespace_differences() {
+    assert_eq!(first_difference("a\nb\n", "a\nb\n"), None);
+    assert_eq!(first_difference("a\nb\n", "a\nc\n"), Some(2));
+    assert_eq!(first_difference("a\nb\n", "a\nb"), Some(3));
+    assert_eq!(first_difference("a\nb", "a\nb\n"), Some(3));
+    assert_eq!(first_difference("a\nb\n", "a\r\nb\r\n"), Some(1));
+}
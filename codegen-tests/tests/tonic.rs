@@ -0,0 +1,78 @@
+#![allow(clippy::result_large_err)]
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+use futures::future;
+use hyper::{Client, Server, StatusCode};
+use hyper::service::make_service_fn;
+use prost_twirp::{ProstTwirpError, TwirpError};
+use tonic::transport::Endpoint;
+
+use codegen_tests::tonic_adapters::multi::alpha::{self, Echo, Note, Reply};
+
+struct EchoService;
+impl Echo for EchoService {
+    fn ping(&self, i: alpha::PTReq<Note>) -> alpha::PTRes<Reply> {
+        let request_id = i.headers.get("x-request-id").and_then(|v| v.to_str().ok()).unwrap_or_default().to_string();
+        Box::pin(future::ok(Reply { payload: format!("{} {}", i.input.payload, request_id) }.into()))
+    }
+
+    fn shout(&self, i: alpha::PTReq<Note>) -> alpha::PTRes<Reply> {
+        let err = match i.input.payload.as_str() {
+            "missing" => TwirpError::new(StatusCode::NOT_FOUND, "not_found", "no such note"),
+            _ => TwirpError::new(StatusCode::BAD_REQUEST, "malformed", "bad note"),
+        };
+        Box::pin(future::err(err.into()))
+    }
+}
+
+async fn grpc_client() -> Box<dyn Echo> {
+    let make_service = make_service_fn(|_conn| async {
+        Ok::<_, Infallible>(<dyn Echo>::new_grpc_server(EchoService))
+    });
+    let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).http2_only(true).serve(make_service);
+    let url = format!("http://{}", server.local_addr());
+    tokio::spawn(server);
+    <dyn Echo>::new_grpc_client(Endpoint::from_shared(url).unwrap().connect().await.unwrap())
+}
+
+fn error_type(err: ProstTwirpError) -> String {
+    match err.root_err() {
+        ProstTwirpError::TwirpError(err) => err.error_type.clone(),
+        err => panic!("expected a Twirp error, got {:?}", err),
+    }
+}
+
+#[tokio::test]
+async fn grpc_round_trip() {
+    let client = grpc_client().await;
+    let mut req = alpha::PTReq::new(Note { payload: "hello".to_string() });
+    req.headers.insert("x-request-id", "42".parse().unwrap());
+    let resp = client.ping(req).await.unwrap();
+    assert_eq!(resp.output, Reply { payload: "hello 42".to_string() });
+}
+
+#[tokio::test]
+async fn grpc_error_codes() {
+    let client = grpc_client().await;
+    let err = client.shout(Note { payload: "missing".to_string() }.into()).await.unwrap_err();
+    assert_eq!(error_type(err), "not_found");
+    // Codes without a gRPC counterpart come back as the closest gRPC one
+    let err = client.shout(Note { payload: "garbled".to_string() }.into()).await.unwrap_err();
+    assert_eq!(error_type(err), "invalid_argument");
+}
+
+#[tokio::test]
+async fn same_impl_serves_twirp() {
+    let make_service = make_service_fn(|_conn| async { Ok::<_, Infallible>(<dyn Echo>::new_server(EchoService)) });
+    let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+    let root_url = format!("http://{}", server.local_addr());
+    tokio::spawn(server);
+
+    let client = <dyn Echo>::new_client(Client::new(), &root_url);
+    let resp = client.ping(Note { payload: "hello".to_string() }.into()).await.unwrap();
+    assert_eq!(resp.output, Reply { payload: "hello ".to_string() });
+    let err = client.shout(Note { payload: "missing".to_string() }.into()).await.unwrap_err();
+    assert_eq!(error_type(err), "not_found");
+}
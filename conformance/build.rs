@@ -0,0 +1,6 @@
+fn main() {
+    println!("cargo:rerun-if-changed=protos");
+    let mut conf = prost_build::Config::new();
+    conf.service_generator(Box::new(prost_twirp::TwirpServiceGenerator::new()));
+    conf.compile_protos(&["protos/clientcompat.proto"], &["protos"]).unwrap();
+}
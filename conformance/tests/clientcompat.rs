@@ -0,0 +1,123 @@
+//! The clientcompat cases: the crate's client against a stand-in server
+
+use hyper::{Client, Method, StatusCode};
+use prost::Message;
+use prost_twirp::ProstTwirpError;
+use serde_json::json;
+
+use conformance::compat::{CompatService, Empty, Req, Resp};
+use conformance::{CannedResponse, StandInServer, ERROR_CODES, METHOD_PATH, NOOP_METHOD_PATH};
+
+fn client(server: &StandInServer) -> Box<dyn CompatService> {
+    <dyn CompatService>::new_client(Client::new(), &server.root_url)
+}
+
+#[tokio::test]
+async fn noop() {
+    let server = StandInServer::start(CannedResponse::new(200, "application/protobuf", vec![])).await;
+    let resp = client(&server).noop_method(Empty {}.into()).await.unwrap();
+    assert_eq!(resp.output, Empty {});
+
+    let req = server.request();
+    assert_eq!(req.method, Method::POST);
+    assert_eq!(req.path, NOOP_METHOD_PATH);
+    assert_eq!(req.headers["content-type"], "application/protobuf");
+    assert!(req.body.is_empty());
+}
+
+#[tokio::test]
+async fn happy_path() {
+    let server = StandInServer::start(CannedResponse::new(200, "application/protobuf", Resp { v: 1 }.encode_to_vec())).
+        await;
+    let resp = client(&server).method(Req { v: "hello".to_string() }.into()).await.unwrap();
+    assert_eq!(resp.status, StatusCode::OK);
+    assert_eq!(resp.output, Resp { v: 1 });
+
+    let req = server.request();
+    assert_eq!(req.path, METHOD_PATH);
+    assert_eq!(req.headers["content-type"], "application/protobuf");
+    assert_eq!(req.headers["content-length"], "7");
+    assert_eq!(req.body, Req { v: "hello".to_string() }.encode_to_vec());
+}
+
+#[tokio::test]
+async fn error_responses() {
+    for (code, status) in ERROR_CODES {
+        let body = json!({ "code": code, "msg": "something went wrong", "meta": { "key": "value" } });
+        let server = StandInServer::start(CannedResponse::new(*status, "application/json", body.to_string())).await;
+        let err = client(&server).method(Req { v: "hello".to_string() }.into()).await.unwrap_err();
+        let err = err.twirp_error().unwrap_or_else(|| panic!("no Twirp error for {}", code));
+        assert_eq!(err.status.as_u16(), *status);
+        assert_eq!(err.error_type, *code);
+        assert_eq!(err.msg, "something went wrong");
+        assert_eq!(err.meta_str("key"), Some("value"));
+    }
+}
+
+#[tokio::test]
+async fn malformed_response_body() {
+    let server = StandInServer::start(CannedResponse::new(200, "application/protobuf", vec![0xff])).await;
+    let err = client(&server).method(Req { v: "hello".to_string() }.into()).await.unwrap_err();
+    assert!(matches!(err.root_err(), ProstTwirpError::ProstDecodeError(_)));
+}
+
+#[tokio::test]
+async fn unexpected_response_content_type() {
+    let server = StandInServer::start(CannedResponse::new(200, "text/html", "<html>captive portal</html>")).await;
+    let err = client(&server).method(Req { v: "hello".to_string() }.into()).await.unwrap_err();
+    match err.root_err() {
+        ProstTwirpError::UnexpectedContentType(content_type) => assert_eq!(content_type, "text/html"),
+        err => panic!("unexpected error {:?}", err),
+    }
+
+    let body = Resp { v: 1 }.encode_to_vec();
+    let server = StandInServer::start(CannedResponse::new(200, "application/x-protobuf; charset=binary", body)).await;
+    let resp = client(&server).method(Req { v: "hello".to_string() }.into()).await.unwrap();
+    assert_eq!(resp.output, Resp { v: 1 });
+}
+
+#[tokio::test]
+async fn non_twirp_error_from_intermediary() {
+    let cases = [
+        (502, "unavailable"), (503, "unavailable"), (504, "unavailable"), (429, "unavailable"),
+        (400, "internal"), (401, "unauthenticated"), (403, "permission_denied"), (404, "bad_route"), (500, "unknown"),
+    ];
+    for (status, code) in &cases {
+        let server = StandInServer::start(CannedResponse::new(*status, "text/html", "<html>proxy says no</html>")).
+            await;
+        let err = client(&server).method(Req { v: "hello".to_string() }.into()).await.unwrap_err();
+        let err = err.twirp_error().unwrap_or_else(|| panic!("no Twirp error for {}", status));
+        let status_code = StatusCode::from_u16(*status).unwrap();
+        assert_eq!(err.status, status_code);
+        assert_eq!(err.error_type, *code);
+        assert_eq!(err.msg, format!("Error from intermediary with HTTP status code {} {:?}", status,
+            status_code.canonical_reason().unwrap()));
+        assert_eq!(err.meta_str("http_error_from_intermediary"), Some("true"));
+        assert_eq!(err.meta_str("status_code"), Some(status.to_string().as_str()));
+        assert_eq!(err.meta_str("body"), Some("<html>proxy says no</html>"));
+    }
+}
+
+#[tokio::test]
+async fn redirect_from_intermediary() {
+    let server = StandInServer::start(CannedResponse::new(302, "text/html", "").
+        with_header("location", "https://example.com/login")).await;
+    let err = client(&server).method(Req { v: "hello".to_string() }.into()).await.unwrap_err();
+    let err = err.twirp_error().unwrap();
+    assert_eq!(err.error_type, "internal");
+    assert_eq!(err.msg, "unexpected HTTP status code 302 \"Found\" received, Location=\"https://example.com/login\"");
+    assert_eq!(err.meta_str("http_error_from_intermediary"), Some("true"));
+    assert_eq!(err.meta_str("status_code"), Some("302"));
+    assert_eq!(err.meta_str("location"), Some("https://example.com/login"));
+}
+
+#[tokio::test]
+async fn json_error_without_valid_code() {
+    let body = json!({ "message": "not a Twirp error" }).to_string();
+    let server = StandInServer::start(CannedResponse::new(500, "application/json", body.clone())).await;
+    let err = client(&server).method(Req { v: "hello".to_string() }.into()).await.unwrap_err();
+    let err = err.twirp_error().unwrap();
+    assert_eq!(err.error_type, "internal");
+    assert_eq!(err.msg, "invalid type returned from server error response: ");
+    assert_eq!(err.meta_str("body"), Some(body.as_str()));
+}
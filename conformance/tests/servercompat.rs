@@ -0,0 +1,123 @@
+//! The servercompat cases: raw requests against the crate's server
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+use futures::future;
+use hyper::{Method, Server, StatusCode};
+use hyper::service::make_service_fn;
+use prost::Message;
+use prost_twirp::TwirpError;
+use serde_json::json;
+
+use conformance::compat::{CompatService, Empty, PTReq, PTRes, Req, Resp};
+use conformance::{send_raw, ERROR_CODES, METHOD_PATH, NOOP_METHOD_PATH};
+
+/// Answers `Method` with the length of the input, or with the error whose code is the input
+struct CompatServer;
+impl CompatService for CompatServer {
+    fn method(&self, i: PTReq<Req>) -> PTRes<Resp> {
+        Box::pin(future::ready(match ERROR_CODES.iter().find(|(code, _)| *code == i.input.v) {
+            Some((code, status)) => Err(TwirpError::new(StatusCode::from_u16(*status).unwrap(), code, "failed").
+                with_meta_str("key", "value").into()),
+            None => Ok(Resp { v: i.input.v.len() as i32 }.into()),
+        }))
+    }
+
+    fn noop_method(&self, _i: PTReq<Empty>) -> PTRes<Empty> {
+        Box::pin(future::ok(Empty {}.into()))
+    }
+}
+
+async fn start() -> String {
+    let make_service = make_service_fn(|_conn| async {
+        Ok::<_, Infallible>(<dyn CompatService>::new_server(CompatServer))
+    });
+    let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+    let root_url = format!("http://{}", server.local_addr());
+    tokio::spawn(server);
+    root_url
+}
+
+#[tokio::test]
+async fn happy_path() {
+    let root_url = start().await;
+    let body = Req { v: "hello".to_string() }.encode_to_vec();
+    let resp = send_raw(&root_url, Method::POST, METHOD_PATH, Some("application/protobuf"), body).await;
+    assert_eq!(resp.status, StatusCode::OK);
+    assert_eq!(resp.content_type(), "application/protobuf");
+    assert_eq!(resp.body, Resp { v: 5 }.encode_to_vec());
+
+    let resp = send_raw(&root_url, Method::POST, NOOP_METHOD_PATH, Some("application/protobuf"), vec![]).await;
+    assert_eq!(resp.status, StatusCode::OK);
+    assert_eq!(resp.headers["content-length"], "0");
+    assert!(resp.body.is_empty());
+}
+
+#[tokio::test]
+async fn error_responses() {
+    let root_url = start().await;
+    for (code, status) in ERROR_CODES {
+        let body = Req { v: code.to_string() }.encode_to_vec();
+        let resp = send_raw(&root_url, Method::POST, METHOD_PATH, Some("application/protobuf"), body).await;
+        assert_eq!(resp.status.as_u16(), *status);
+        assert_eq!(resp.content_type(), "application/json");
+        assert_eq!(resp.json(), json!({ "code": code, "msg": "failed", "meta": { "key": "value" } }));
+    }
+}
+
+#[tokio::test]
+async fn unknown_route() {
+    let root_url = start().await;
+    let path = "/twirp/twirp.clientcompat.CompatService/Missing";
+    let resp = send_raw(&root_url, Method::POST, path, Some("application/protobuf"), vec![]).await;
+    assert_eq!(resp.status, StatusCode::NOT_FOUND);
+    assert_eq!(resp.content_type(), "application/json");
+    assert_eq!(resp.json(), json!({ "code": "bad_route", "msg": format!("no handler for path {:?}", path) }));
+}
+
+#[tokio::test]
+async fn bad_method() {
+    let root_url = start().await;
+    let resp = send_raw(&root_url, Method::GET, METHOD_PATH, Some("application/protobuf"), vec![]).await;
+    assert_eq!(resp.status, StatusCode::NOT_FOUND);
+    assert_eq!(resp.json(), json!({ "code": "bad_route", "msg": "unsupported method GET (only POST is allowed)" }));
+}
+
+#[tokio::test]
+async fn bad_content_type() {
+    let root_url = start().await;
+    let resp = send_raw(&root_url, Method::POST, METHOD_PATH, Some("text/plain"), vec![]).await;
+    assert_eq!(resp.status, StatusCode::NOT_FOUND);
+    assert_eq!(resp.json(), json!({ "code": "bad_route", "msg": "unexpected Content-Type: \"text/plain\"" }));
+
+    let resp = send_raw(&root_url, Method::POST, METHOD_PATH, None, vec![]).await;
+    assert_eq!(resp.status, StatusCode::NOT_FOUND);
+    assert_eq!(resp.json()["code"], "bad_route");
+}
+
+#[tokio::test]
+async fn malformed_body() {
+    let root_url = start().await;
+    let resp = send_raw(&root_url, Method::POST, METHOD_PATH, Some("application/protobuf"), vec![0xff]).await;
+    assert_eq!(resp.status, StatusCode::BAD_REQUEST);
+    assert_eq!(resp.content_type(), "application/json");
+    assert_eq!(resp.json(), json!({ "code": "malformed", "msg": "the request could not be decoded" }));
+}
+
+#[tokio::test]
+async fn content_type_parameters_and_aliases() {
+    let root_url = start().await;
+    let cases = [
+        ("application/protobuf; charset=binary", "application/protobuf"),
+        ("Application/Protobuf", "application/protobuf"),
+        ("application/x-protobuf", "application/x-protobuf"),
+    ];
+    for (content_type, expected) in &cases {
+        let body = Req { v: "hello".to_string() }.encode_to_vec();
+        let resp = send_raw(&root_url, Method::POST, METHOD_PATH, Some(content_type), body).await;
+        assert_eq!(resp.status, StatusCode::OK, "for {}", content_type);
+        assert_eq!(resp.content_type(), *expected);
+        assert_eq!(resp.body, Resp { v: 5 }.encode_to_vec());
+    }
+}
@@ -6,7 +6,7 @@ use futures::future;
 use hyper::{Client, StatusCode};
 use hyper::server::Server;
 use hyper::service::make_service_fn;
-use prost_twirp::TwirpError;
+use prost_twirp::{ProstTwirpError, TwirpError};
 use tokio::sync::oneshot;
 use tokio::time;
 
@@ -50,7 +50,10 @@ async fn main() {
             let service_client = &service_client;
             async move {
                 let res = service_client.make_hat(service::Size { inches }.into()).await;
-                let res = res.map(|v| v.output).map_err(|e| e.root_err());
+                let res = res.map(|v| v.output).map_err(|e| match e.root_err() {
+                    ProstTwirpError::TwirpError(err) => format!("{} with bounds {:?}", err, err.meta_as::<MinMaxSize>()),
+                    err => format!("{:?}", err),
+                });
                 Ok::<(), ()>(println!("For size {}: {:?}", inches, res))
             }
         }));
@@ -64,13 +67,13 @@ async fn main() {
 pub struct HaberdasherService;
 impl service::Haberdasher for HaberdasherService {
     fn make_hat(&self, i: service::PTReq<service::Size>) -> service::PTRes<service::Hat> {
-        Box::pin(future::result(
+        Box::pin(future::ready(
             if i.input.inches < 1 {
-                Err(TwirpError::new_meta(StatusCode::BAD_REQUEST, "too_small", "Size too small",
-                    serde_json::to_value(MinMaxSize { min: 1, max: 10 }).ok()).into())
+                Err(TwirpError::new(StatusCode::BAD_REQUEST, "out_of_range", "Size too small").
+                    with_meta(&MinMaxSize { min: 1, max: 10 }).unwrap().into())
             } else if i.input.inches > 10 {
-                Err(TwirpError::new_meta(StatusCode::BAD_REQUEST, "too_large", "Size too large",
-                    serde_json::to_value(MinMaxSize { min: 1, max: 10 }).ok()).into())
+                Err(TwirpError::new(StatusCode::BAD_REQUEST, "out_of_range", "Size too large").
+                    with_meta(&MinMaxSize { min: 1, max: 10 }).unwrap().into())
             } else {
                 Ok(service::Hat { size: i.input.inches, color: "blue".to_string(), name: "fedora".to_string() }.into())
             }
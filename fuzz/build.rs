@@ -0,0 +1,5 @@
+fn main() {
+    let mut conf = prost_build::Config::new();
+    conf.service_generator(Box::new(prost_twirp::TwirpServiceGenerator::new()));
+    conf.compile_protos(&["../examples/service.proto"], &["../examples"]).unwrap();
+}
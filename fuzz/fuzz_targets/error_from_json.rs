@@ -0,0 +1,19 @@
+#![no_main]
+
+use std::collections::BTreeMap;
+
+use hyper::StatusCode;
+use libfuzzer_sys::fuzz_target;
+use prost_twirp::TwirpError;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(err) = TwirpError::from_json_bytes(StatusCode::BAD_REQUEST, data) {
+        let _ = err.meta_map();
+        let _ = err.meta_as::<BTreeMap<String, String>>();
+        // Whatever was read must be writable again
+        let json = err.to_json_bytes().unwrap();
+        let reread = TwirpError::from_json_bytes(err.status, &json).unwrap();
+        assert_eq!(reread.meta_map(), err.meta_map());
+        let _ = err.to_hyper_resp().unwrap();
+    }
+});
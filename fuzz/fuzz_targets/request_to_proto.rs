@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use prost_twirp::ServiceRequest;
+use prost_twirp_fuzz::service::Size;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = ServiceRequest::new(data.to_vec()).to_proto::<Size>();
+});
@@ -0,0 +1,16 @@
+#![no_main]
+
+use hyper::StatusCode;
+use libfuzzer_sys::fuzz_target;
+use prost_twirp::ServiceResponse;
+use prost_twirp_fuzz::service::Hat;
+
+fuzz_target!(|input: (u16, &[u8])| {
+    let (status, output) = input;
+    // Non-success statuses take the error JSON path
+    let mut resp = ServiceResponse::new(output.to_vec());
+    resp.status = StatusCode::from_u16(100 + status % 900).unwrap();
+    if let Err(err) = resp.to_proto::<Hat>() {
+        let _ = err.cause_chain();
+    }
+});
@@ -0,0 +1,93 @@
+#![no_main]
+
+//! Random hyper requests through a generated server, covering the validation path of `HyperServer::call`
+
+use std::sync::Arc;
+
+use arbitrary::Arbitrary;
+use hyper::body::Bytes;
+use hyper::service::Service;
+use hyper::{Body, Method, Request};
+use libfuzzer_sys::fuzz_target;
+use prost_twirp::{HyperServer, TwirpError};
+use prost_twirp_fuzz::service::HaberdasherServer;
+use prost_twirp_fuzz::HaberdasherService;
+
+#[derive(Arbitrary, Debug)]
+enum FuzzMethod { Post, Get, Other(String) }
+
+#[derive(Arbitrary, Debug)]
+enum FuzzPath { MakeHat, Prefix(String), Any(String) }
+
+#[derive(Arbitrary, Debug)]
+enum FuzzContentType { Protobuf, Json, Any(String), Missing }
+
+/// The largest request body the server reads
+const MAX_BODY_SIZE: usize = 64 * 1024;
+
+#[derive(Arbitrary, Debug)]
+enum FuzzBody {
+    Full(Vec<u8>),
+    /// The chunk repeated without end, which the server must stop reading at `MAX_BODY_SIZE`
+    Endless(Vec<u8>),
+}
+
+#[derive(Arbitrary, Debug)]
+struct FuzzRequest {
+    method: FuzzMethod,
+    path: FuzzPath,
+    content_type: FuzzContentType,
+    headers: Vec<(String, Vec<u8>)>,
+    body: FuzzBody,
+}
+
+impl FuzzRequest {
+    fn to_hyper(&self) -> Option<Request<Body>> {
+        let method = match &self.method {
+            FuzzMethod::Post => Method::POST,
+            FuzzMethod::Get => Method::GET,
+            FuzzMethod::Other(method) => Method::from_bytes(method.as_bytes()).ok()?,
+        };
+        let path = match &self.path {
+            FuzzPath::MakeHat => "/twirp/twitch.twirp.example.Haberdasher/MakeHat".to_string(),
+            FuzzPath::Prefix(rest) => format!("/twirp/twitch.twirp.example.Haberdasher/{}", rest),
+            FuzzPath::Any(path) => path.clone(),
+        };
+        let mut builder = Request::builder().method(method).uri(path);
+        builder = match &self.content_type {
+            FuzzContentType::Protobuf => builder.header("content-type", "application/protobuf"),
+            FuzzContentType::Json => builder.header("content-type", "application/json"),
+            FuzzContentType::Any(content_type) => builder.header("content-type", content_type.as_str()),
+            FuzzContentType::Missing => builder,
+        };
+        for (name, value) in &self.headers {
+            builder = builder.header(name.as_str(), value.as_slice());
+        }
+        let body = match &self.body {
+            FuzzBody::Full(body) => Body::from(body.clone()),
+            FuzzBody::Endless(chunk) => {
+                let chunk = Bytes::from(if chunk.is_empty() { vec![0] } else { chunk.clone() });
+                Body::wrap_stream(futures::stream::iter(std::iter::repeat(chunk).map(Ok::<_, std::io::Error>)))
+            },
+        };
+        builder.body(body).ok()
+    }
+}
+
+fuzz_target!(|input: FuzzRequest| {
+    let req = match input.to_hyper() {
+        Some(req) => req,
+        None => return,
+    };
+    let mut server = HyperServer::new(HaberdasherServer(Arc::new(HaberdasherService))).with_dev_mode(true).
+        with_max_body_size(MAX_BODY_SIZE);
+    let resp = futures::executor::block_on(server.call(req)).unwrap();
+    let status = resp.status();
+    let body = futures::executor::block_on(hyper::body::to_bytes(resp.into_body())).unwrap();
+    // Every failure must be answered with a valid Twirp error
+    if !status.is_success() {
+        let err = TwirpError::from_json_bytes(status, &body).unwrap();
+        assert!(!err.error_type.is_empty());
+    }
+    assert!(!(status.is_success() && matches!(input.body, FuzzBody::Endless(_))));
+});
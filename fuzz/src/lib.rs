@@ -0,0 +1,23 @@
+//! Shared pieces of the fuzz targets
+
+#![allow(clippy::result_large_err)]
+
+use futures::future;
+
+pub mod service {
+    include!(concat!(env!("OUT_DIR"), "/twitch.twirp.example.rs"));
+}
+
+/// A service answering with a hat for sizes 1 to 10 and a Twirp error with meta for the others
+pub struct HaberdasherService;
+
+impl service::Haberdasher for HaberdasherService {
+    fn make_hat(&self, i: service::PTReq<service::Size>) -> service::PTRes<service::Hat> {
+        Box::pin(future::ready(if (1..=10).contains(&i.input.inches) {
+            Ok(service::Hat { size: i.input.inches, color: "blue".to_string(), name: "fedora".to_string() }.into())
+        } else {
+            Err(prost_twirp::TwirpError::new(hyper::StatusCode::BAD_REQUEST, "invalid_argument", "bad size").
+                with_meta_str("argument", "inches").into())
+        }))
+    }
+}
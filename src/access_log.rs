@@ -0,0 +1,128 @@
+use std::fmt;
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde_json::{json, Map, Value};
+use crate::{AccessLogRecord, AccessLogSink};
+
+impl AccessLogRecord {
+    /// The record as a flat JSON object, with the timestamp in RFC 3339 and the duration in milliseconds
+    pub fn to_json(&self) -> Value {
+        let headers: Map<String, Value> =
+            self.headers.iter().map(|(name, value)| (name.clone(), Value::String(value.clone()))).collect();
+        json!({
+            "timestamp": rfc3339(self.timestamp),
+            "peer": self.peer.map(|v| v.to_string()),
+            "http_method": self.http_method.as_str(),
+            "path": self.rpc.path,
+            "service": self.rpc.service,
+            "method": self.rpc.method,
+            "content_type": self.content_type,
+            "request_size": self.outcome.request_size,
+            "response_size": self.outcome.response_size,
+            "status": self.outcome.status.map(|v| v.as_u16()),
+            "twirp_code": self.outcome.code(),
+            "duration_ms": self.outcome.latency.as_secs_f64() * 1000.0,
+            "headers": headers,
+        })
+    }
+}
+
+/// Displays the record as a single line of `key=value` pairs
+impl fmt::Display for AccessLogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "timestamp={} peer={} http_method={} path={:?} content_type={:?} request_size={} response_size={} \
+                status={} twirp_code={} duration_ms={:.3}",
+            rfc3339(self.timestamp), self.peer.map_or_else(|| "-".to_string(), |v| v.to_string()), self.http_method,
+            self.rpc.path, self.content_type.as_deref().unwrap_or(""), self.outcome.request_size,
+            self.outcome.response_size, self.outcome.status.map_or(0, |v| v.as_u16()), self.outcome.code(),
+            self.outcome.latency.as_secs_f64() * 1000.0)?;
+        for (name, value) in &self.headers {
+            write!(f, " header.{}={:?}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// An access log sink writing every record as a line of JSON
+#[derive(Debug)]
+pub struct JsonLinesSink<W: Write + Send>(Mutex<W>);
+
+impl<W: Write + Send> JsonLinesSink<W> {
+    /// Create a new sink writing to the given writer, e.g. `std::io::stdout()` or a file
+    pub fn new(writer: W) -> JsonLinesSink<W> { JsonLinesSink(Mutex::new(writer)) }
+}
+
+impl<W: Write + Send> AccessLogSink for JsonLinesSink<W> {
+    fn log(&self, record: &AccessLogRecord) {
+        let mut line = record.to_json().to_string();
+        line.push('\n');
+        let mut writer = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        // There is nobody to report a failed write to
+        let _ = writer.write_all(line.as_bytes()).and_then(|_| writer.flush());
+    }
+}
+
+/// An access log sink writing every record to the `log` crate facade, with the `prost_twirp::access` target
+///
+/// Successful requests are logged at info level and failed ones at warn level.
+#[cfg(feature = "log")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogSink;
+
+#[cfg(feature = "log")]
+impl AccessLogSink for LogSink {
+    fn log(&self, record: &AccessLogRecord) {
+        let level = if record.outcome.error_code.is_none() { log::Level::Info } else { log::Level::Warn };
+        log::log!(target: "prost_twirp::access", level, "{}", record);
+    }
+}
+
+/// An access log sink emitting every record as a `tracing` event with the `prost_twirp::access` target
+///
+/// The record's values are separate fields of the event, with the allowed headers as a single `headers` field.
+#[cfg(feature = "tracing")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingSink;
+
+#[cfg(feature = "tracing")]
+impl AccessLogSink for TracingSink {
+    fn log(&self, record: &AccessLogRecord) {
+        tracing::info!(
+            target: "prost_twirp::access",
+            timestamp = %rfc3339(record.timestamp),
+            peer = ?record.peer,
+            http_method = %record.http_method,
+            path = %record.rpc.path,
+            service = %record.rpc.service,
+            method = %record.rpc.method,
+            content_type = ?record.content_type,
+            request_size = record.outcome.request_size as u64,
+            response_size = record.outcome.response_size as u64,
+            status = ?record.outcome.status.map(|v| v.as_u16()),
+            twirp_code = %record.outcome.code(),
+            duration_ms = record.outcome.latency.as_secs_f64() * 1000.0,
+            headers = ?record.headers,
+        );
+    }
+}
+
+/// Format the time as an RFC 3339 UTC timestamp with milliseconds
+fn rfc3339(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, secs_of_day) = ((secs / 86400) as i64, secs % 86400);
+    // Civil date from days since the epoch, see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z", year, month, day, secs_of_day / 3600, secs_of_day / 60 % 60,
+        secs_of_day % 60, since_epoch.subsec_millis())
+}
+
@@ -0,0 +1,148 @@
+use std::future::{ready, Future};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use hyper::{body, Body, Method, Request, Response, StatusCode};
+use hyper::header::{self, HeaderName, HeaderValue};
+use hyper::service::Service;
+use crate::{media_type, HyperServer, HyperService, PTRes, ServiceDescriptor, ServiceRequest, ServiceResponse,
+    TwirpError, CONNECT_CONTENT_TYPE};
+
+const PROTOCOL_VERSION: HeaderName = HeaderName::from_static("connect-protocol-version");
+const TIMEOUT_MS: HeaderName = HeaderName::from_static("connect-timeout-ms");
+
+/// A hyper service serving a `HyperServer` over both Twirp and the unary subset of the Connect protocol
+///
+/// Requests to `/twirp/...` are passed to the server as is. Connect requests to `/package.Service/Method` with
+/// `application/proto` bodies are routed to the matching Twirp method, and their responses and errors are turned into
+/// Connect ones, see `TwirpError::to_connect_resp_raw`. The `Connect-Timeout-Ms` of Connect requests is enforced, with
+/// a `deadline_exceeded` error when the service takes longer.
+///
+/// The hooks, access log and error handler of the server see Connect requests with their Twirp route and Twirp error
+/// codes. Connect requests with an unsupported HTTP method, content type or protocol version are answered before
+/// reaching the server.
+pub struct ConnectServer<T: 'static + HyperService> {
+    server: HyperServer<Deadline<T>>,
+}
+
+impl<T: 'static + HyperService> ConnectServer<T> {
+    /// Create a new Connect and Twirp service for the given server
+    pub fn new(server: HyperServer<T>) -> ConnectServer<T> {
+        ConnectServer { server: server.map_service(Deadline) }
+    }
+}
+
+impl<T: 'static + HyperService> Clone for ConnectServer<T> {
+    fn clone(&self) -> ConnectServer<T> { ConnectServer { server: self.server.clone() } }
+}
+
+impl<T: Send + Sync + 'static + HyperService> Service<Request<Body>> for ConnectServer<T> {
+    type Response = Response<Body>;
+    type Error = hyper::Error;
+    type Future = Pin<Box<dyn Future<Output=Result<Response<Body>, hyper::Error>>+Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> { self.server.poll_ready(cx) }
+
+    fn call(&mut self, mut req: Request<Body>) -> Self::Future {
+        if req.uri().path().starts_with("/twirp/") {
+            // Timeouts are only part of the Connect protocol
+            req.headers_mut().remove(TIMEOUT_MS);
+            return self.server.call(req);
+        }
+        if let Err(resp) = to_twirp_request(&mut req) {
+            return Box::pin(ready(Ok(resp)));
+        }
+        let resp = self.server.call(req);
+        Box::pin(async move { Ok(to_connect_response(resp.await?).await) })
+    }
+}
+
+/// Check the Connect request and turn it into the matching Twirp one, or the response to reject it with
+#[allow(clippy::result_large_err)]
+fn to_twirp_request(req: &mut Request<Body>) -> Result<(), Response<Body>> {
+    if req.method() != Method::POST {
+        let mut resp = empty_response(StatusCode::METHOD_NOT_ALLOWED);
+        resp.headers_mut().insert(header::ALLOW, HeaderValue::from_static("POST"));
+        return Err(resp);
+    }
+    if req.headers().get(header::CONTENT_TYPE).and_then(media_type).as_deref() != Some(CONNECT_CONTENT_TYPE) {
+        let mut resp = empty_response(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        resp.headers_mut().
+            insert(HeaderName::from_static("accept-post"), HeaderValue::from_static(CONNECT_CONTENT_TYPE));
+        return Err(resp);
+    }
+    if let Some(version) = req.headers().get(PROTOCOL_VERSION).filter(|v| *v != "1") {
+        return Err(invalid_argument(&format!("connect-protocol-version must be \"1\": got {:?}", version)));
+    }
+    if let Some(timeout) = req.headers().get(TIMEOUT_MS).filter(|v| parse_timeout(v).is_none()) {
+        return Err(invalid_argument(&format!("invalid connect-timeout-ms: {:?}", timeout)));
+    }
+    let path_and_query = format!("/twirp{}", req.uri().path_and_query().map_or("/", |v| v.as_str()));
+    let mut parts = req.uri().clone().into_parts();
+    parts.path_and_query = Some(path_and_query.parse().map_err(|_| empty_response(StatusCode::NOT_FOUND))?);
+    *req.uri_mut() = hyper::Uri::from_parts(parts).map_err(|_| empty_response(StatusCode::NOT_FOUND))?;
+    req.headers_mut().insert(header::CONTENT_TYPE, HeaderValue::from_static("application/protobuf"));
+    Ok(())
+}
+
+/// Turn the Twirp response of the server into a Connect one
+async fn to_connect_response(mut resp: Response<Body>) -> Response<Body> {
+    if resp.status().is_success() {
+        resp.headers_mut().insert(header::CONTENT_TYPE, HeaderValue::from_static(CONNECT_CONTENT_TYPE));
+        return resp;
+    }
+    let version = resp.version();
+    let headers = resp.headers().clone();
+    let status = resp.status();
+    let mut err = match body::to_bytes(resp.into_body()).await {
+        Ok(body) => TwirpError::from_response(&ServiceResponse { version, headers, status, output: body.to_vec() }),
+        Err(err) => TwirpError::internal(err),
+    };
+    // Codes of the service that aren't in the Twirp spec are `unknown` in Connect rather than `internal`
+    if let Some(code) = err.meta_str("invalid_code") {
+        err.error_type = code.to_string();
+    }
+    err.to_connect_resp_raw().to_hyper_raw().unwrap_or_else(|_| empty_response(StatusCode::INTERNAL_SERVER_ERROR))
+}
+
+fn invalid_argument(msg: &str) -> Response<Body> {
+    TwirpError::new(StatusCode::BAD_REQUEST, "invalid_argument", msg).to_connect_resp_raw().to_hyper_raw().
+        unwrap_or_else(|_| empty_response(StatusCode::BAD_REQUEST))
+}
+
+fn empty_response(status: StatusCode) -> Response<Body> {
+    let mut resp = Response::new(Body::empty());
+    *resp.status_mut() = status;
+    resp
+}
+
+/// The `Connect-Timeout-Ms` value, which has at most 10 digits
+fn parse_timeout(value: &HeaderValue) -> Option<Duration> {
+    let value = value.to_str().ok()?;
+    if value.is_empty() || value.len() > 10 || !value.bytes().all(|b| b.is_ascii_digit()) { return None; }
+    value.parse().ok().map(Duration::from_millis)
+}
+
+/// A service failing with `deadline_exceeded` when it takes longer than the request's `Connect-Timeout-Ms`
+struct Deadline<T>(Arc<T>);
+
+#[allow(clippy::result_large_err)]
+impl<T: 'static + HyperService> HyperService for Deadline<T> {
+    fn handle(&self, req: ServiceRequest<Vec<u8>>) -> PTRes<Vec<u8>> {
+        match req.headers.get(TIMEOUT_MS).and_then(parse_timeout) {
+            None => self.0.handle(req),
+            Some(duration) => {
+                let resp = self.0.handle(req);
+                Box::pin(async move {
+                    tokio::time::timeout(duration, resp).await.unwrap_or_else(|_| {
+                        Err(TwirpError::new(StatusCode::REQUEST_TIMEOUT, "deadline_exceeded",
+                            "the request timed out").into())
+                    })
+                })
+            },
+        }
+    }
+
+    fn descriptor(&self) -> Option<&'static ServiceDescriptor> { self.0.descriptor() }
+}
@@ -0,0 +1,124 @@
+use hyper::StatusCode;
+use hyper::header::{self, HeaderMap, HeaderValue};
+use tonic::metadata::MetadataMap;
+use tonic::{Code, Status};
+use crate::{ProstTwirpError, ServiceRequest, ServiceResponse, TwirpError};
+
+impl<T> ServiceRequest<T> {
+    /// Create a request for the given Twirp route from a tonic gRPC request, with the metadata as headers
+    pub fn from_tonic(path: &str, req: tonic::Request<T>) -> ServiceRequest<T> {
+        let headers = req.metadata().clone().into_headers();
+        let mut service_req = ServiceRequest::new(req.into_inner());
+        service_req.uri = path.parse().unwrap_or_default();
+        service_req.headers = with_protobuf_content_type(headers);
+        service_req
+    }
+
+    /// Turn the request into a tonic gRPC request, with the headers other than `Content-Type` and `Content-Length` as
+    /// metadata
+    pub fn into_tonic(self) -> tonic::Request<T> {
+        let mut req = tonic::Request::new(self.input);
+        *req.metadata_mut() = to_metadata(self.headers);
+        req
+    }
+}
+
+impl<T> ServiceResponse<T> {
+    /// Create a response from a tonic gRPC response, with the metadata as headers
+    pub fn from_tonic(resp: tonic::Response<T>) -> ServiceResponse<T> {
+        let headers = resp.metadata().clone().into_headers();
+        let mut service_resp = ServiceResponse::new(resp.into_inner());
+        service_resp.headers = with_protobuf_content_type(headers);
+        service_resp
+    }
+
+    /// Turn the response into a tonic gRPC response, with the headers other than `Content-Type` and `Content-Length` as
+    /// metadata
+    pub fn into_tonic(self) -> tonic::Response<T> {
+        let mut resp = tonic::Response::new(self.output);
+        *resp.metadata_mut() = to_metadata(self.headers);
+        resp
+    }
+}
+
+fn with_protobuf_content_type(mut headers: HeaderMap) -> HeaderMap {
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/protobuf"));
+    headers
+}
+
+fn to_metadata(mut headers: HeaderMap) -> MetadataMap {
+    headers.remove(header::CONTENT_TYPE);
+    headers.remove(header::CONTENT_LENGTH);
+    MetadataMap::from_headers(headers)
+}
+
+/// The gRPC status for the Twirp error, with the matching code and the message
+///
+/// `malformed` becomes `INVALID_ARGUMENT`, `bad_route` becomes `UNIMPLEMENTED` and codes outside of the Twirp spec
+/// become `UNKNOWN`. The meta isn't carried over.
+impl From<TwirpError> for Status {
+    fn from(err: TwirpError) -> Status {
+        let code = match err.error_type.as_str() {
+            "canceled" => Code::Cancelled,
+            "invalid_argument" | "malformed" => Code::InvalidArgument,
+            "deadline_exceeded" => Code::DeadlineExceeded,
+            "not_found" => Code::NotFound,
+            "already_exists" => Code::AlreadyExists,
+            "permission_denied" => Code::PermissionDenied,
+            "unauthenticated" => Code::Unauthenticated,
+            "resource_exhausted" => Code::ResourceExhausted,
+            "failed_precondition" => Code::FailedPrecondition,
+            "aborted" => Code::Aborted,
+            "out_of_range" => Code::OutOfRange,
+            "unimplemented" | "bad_route" => Code::Unimplemented,
+            "internal" => Code::Internal,
+            "unavailable" => Code::Unavailable,
+            "dataloss" => Code::DataLoss,
+            _ => Code::Unknown,
+        };
+        Status::new(code, err.msg)
+    }
+}
+
+/// The gRPC status for the error, treated like the `DefaultErrorHandler` treats it
+impl From<ProstTwirpError> for Status {
+    fn from(err: ProstTwirpError) -> Status {
+        match err.root_err() {
+            ProstTwirpError::TwirpError(err) => err.into(),
+            ProstTwirpError::ProstDecodeError(_) | ProstTwirpError::JsonDecodeError(_) =>
+                Status::invalid_argument("the request could not be decoded"),
+            _ => Status::internal("Internal Error"),
+        }
+    }
+}
+
+/// The Twirp error for the gRPC status, with the matching code, the HTTP status of that code in the Twirp spec and the
+/// status as the cause
+impl From<Status> for TwirpError {
+    fn from(status: Status) -> TwirpError {
+        let (code, http_status) = match status.code() {
+            Code::Cancelled => ("canceled", StatusCode::REQUEST_TIMEOUT),
+            Code::InvalidArgument => ("invalid_argument", StatusCode::BAD_REQUEST),
+            Code::DeadlineExceeded => ("deadline_exceeded", StatusCode::REQUEST_TIMEOUT),
+            Code::NotFound => ("not_found", StatusCode::NOT_FOUND),
+            Code::AlreadyExists => ("already_exists", StatusCode::CONFLICT),
+            Code::PermissionDenied => ("permission_denied", StatusCode::FORBIDDEN),
+            Code::Unauthenticated => ("unauthenticated", StatusCode::UNAUTHORIZED),
+            Code::ResourceExhausted => ("resource_exhausted", StatusCode::TOO_MANY_REQUESTS),
+            Code::FailedPrecondition => ("failed_precondition", StatusCode::PRECONDITION_FAILED),
+            Code::Aborted => ("aborted", StatusCode::CONFLICT),
+            Code::OutOfRange => ("out_of_range", StatusCode::BAD_REQUEST),
+            Code::Unimplemented => ("unimplemented", StatusCode::NOT_IMPLEMENTED),
+            Code::Internal => ("internal", StatusCode::INTERNAL_SERVER_ERROR),
+            Code::Unavailable => ("unavailable", StatusCode::SERVICE_UNAVAILABLE),
+            Code::DataLoss => ("dataloss", StatusCode::INTERNAL_SERVER_ERROR),
+            Code::Ok | Code::Unknown => ("unknown", StatusCode::INTERNAL_SERVER_ERROR),
+        };
+        let msg = status.message().to_string();
+        TwirpError::new(http_status, code, &msg).with_cause(status)
+    }
+}
+
+impl From<Status> for ProstTwirpError {
+    fn from(status: Status) -> ProstTwirpError { ProstTwirpError::TwirpError(status.into()) }
+}
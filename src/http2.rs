@@ -0,0 +1,23 @@
+use hyper::client::{Builder, Client};
+
+/// How a client speaks HTTP/2
+///
+/// Servers need nothing extra: with the `http2` feature, hyper's server answers both HTTP/1.1 and prior knowledge
+/// HTTP/2 on the same connection, or only HTTP/2 with `hyper::server::Builder::http2_only`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Http2 {
+    /// Speak HTTP/2 without negotiating it first, which is how h2c is used over plain TCP
+    PriorKnowledge,
+    /// Speak HTTP/2 when the connector negotiates it with ALPN during the TLS handshake, HTTP/1.1 otherwise
+    Alpn,
+}
+
+impl Http2 {
+    /// A hyper client builder speaking HTTP/2 this way, e.g. `Http2::PriorKnowledge.client_builder().build_http()`
+    /// for the client of `HyperClient::new`
+    pub fn client_builder(self) -> Builder {
+        let mut builder = Client::builder();
+        builder.http2_only(self == Http2::PriorKnowledge);
+        builder
+    }
+}
@@ -1,12 +1,57 @@
 //! Prost Twirp is a code generator and set of utilities for calling and serving
 //! [Twirp](https://github.com/twitchtv/twirp) services in Rust, using the [prost](https://github.com/danburkert/prost/)
 //! and [hyper](https://github.com/hyperium/hyper) libraries.
-//! 
+//!
 //! See [the github project](https://github.com/cretz/prost-twirp) for more info.
 
 mod service_run;
 pub use service_run::*;
 
+mod access_log;
+pub use access_log::JsonLinesSink;
+#[cfg(feature = "log")]
+pub use access_log::LogSink;
+#[cfg(feature = "tracing")]
+pub use access_log::TracingSink;
+
+mod connect;
+pub use connect::ConnectServer;
+
+#[cfg(feature = "tonic")]
+mod grpc;
+
+#[cfg(feature = "http2")]
+mod http2;
+#[cfg(feature = "http2")]
+pub use http2::Http2;
+
+mod limits;
+pub use limits::{ConcurrencyLimits, GLOBAL_LIMIT};
+
+mod meta;
+
+mod metrics;
+pub use metrics::{MetricsHandler, TwirpMetrics, DEFAULT_BUCKETS, PROMETHEUS_CONTENT_TYPE, UNKNOWN_METHOD};
+
+#[cfg(feature = "rustls")]
+mod tls;
+#[cfg(feature = "rustls")]
+pub use tls::{https_connector, load_certificates, load_private_key, tls_client_config, tls_server_config,
+    HttpsConnector, TlsConn, TlsIncoming};
+#[cfg(feature = "rustls")]
+pub use rustls;
+
+#[cfg(unix)]
+mod unix;
+#[cfg(unix)]
+pub use unix::{serve_unix, UnixConn, UnixConnector, UnixIncoming};
+
+mod router;
+pub use router::TwirpRouter;
+
+mod server;
+pub use server::{TwirpServerBuilder, TwirpServerHandle};
+
 #[cfg(feature = "service-gen")]
 mod service_gen;
 #[cfg(feature = "service-gen")]
@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use hyper::StatusCode;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+use crate::{Admission, ConcurrencyLimit, LimitOutcome, RpcInfo, TwirpError};
+
+/// The name of the limit on all requests in `LimitOutcome::limit`
+pub const GLOBAL_LIMIT: &str = "global";
+
+/// A `ConcurrencyLimit` on the requests in flight, across all methods and per method
+///
+/// Requests beyond a limit are shed right away with a `resource_exhausted` error. With a queue timeout they wait that
+/// long for room instead, and are shed with an `unavailable` error if there is none by then. A request is counted
+/// against the limit of its method before the global one, so requests waiting for a busy method don't hold room others
+/// could use.
+#[derive(Clone, Default)]
+pub struct ConcurrencyLimits {
+    global: Option<Arc<Semaphore>>,
+    methods: BTreeMap<String, Arc<Semaphore>>,
+    queue_timeout: Option<Duration>,
+}
+
+impl ConcurrencyLimits {
+    /// Create limits that don't limit anything yet
+    pub fn new() -> ConcurrencyLimits { ConcurrencyLimits::default() }
+
+    /// Limit the requests in flight across all methods
+    pub fn with_max_in_flight(mut self, max: usize) -> ConcurrencyLimits {
+        self.global = Some(Arc::new(Semaphore::new(max)));
+        self
+    }
+
+    /// Limit the requests in flight for the method at the given path
+    ///
+    /// The path is the route of the method, e.g. `/twirp/twitch.twirp.example.Haberdasher/MakeHat`, as found in
+    /// `MethodDescriptor::path`.
+    pub fn with_method_max_in_flight(mut self, path: &str, max: usize) -> ConcurrencyLimits {
+        self.methods.insert(path.to_string(), Arc::new(Semaphore::new(max)));
+        self
+    }
+
+    /// Let requests beyond a limit wait up to the given time for room instead of shedding them right away
+    pub fn with_queue_timeout(mut self, queue_timeout: Duration) -> ConcurrencyLimits {
+        self.queue_timeout = Some(queue_timeout);
+        self
+    }
+}
+
+impl std::fmt::Debug for ConcurrencyLimits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let methods: Vec<_> = self.methods.keys().collect();
+        f.debug_struct("ConcurrencyLimits").field("global", &self.global.is_some()).field("methods", &methods).
+            field("queue_timeout", &self.queue_timeout).finish()
+    }
+}
+
+impl ConcurrencyLimit for ConcurrencyLimits {
+    fn acquire(&self, rpc: &RpcInfo) -> Pin<Box<dyn Future<Output=Admission> + Send>> {
+        let limits: Vec<_> = self.methods.get_key_value(&rpc.path).map(|(k, v)| (k.clone(), v.clone())).into_iter().
+            chain(self.global.clone().map(|v| (GLOBAL_LIMIT.to_string(), v))).collect();
+        let queue_timeout = self.queue_timeout;
+        Box::pin(async move {
+            let start = Instant::now();
+            let mut permits: Vec<OwnedSemaphorePermit> = Vec::with_capacity(limits.len());
+            let mut limited = Vec::new();
+            for (limit, semaphore) in limits {
+                if let Ok(permit) = semaphore.clone().try_acquire_owned() {
+                    permits.push(permit);
+                    continue;
+                }
+                // The queue timeout covers the wait for all limits, but the wait is reported per limit
+                let waiting = Instant::now();
+                let permit = match queue_timeout {
+                    Some(timeout) => tokio::time::timeout_at(start + timeout, semaphore.acquire_owned()).await.
+                        ok().and_then(Result::ok),
+                    None => None,
+                };
+                let outcome = LimitOutcome { limit, waited: waiting.elapsed(), shed: permit.is_none() };
+                match permit {
+                    Some(permit) => permits.push(permit),
+                    None => {
+                        let err = if queue_timeout.is_some() {
+                            TwirpError::new(StatusCode::SERVICE_UNAVAILABLE, "unavailable",
+                                &format!("timed out waiting for the {} concurrency limit", outcome.limit))
+                        } else {
+                            TwirpError::new(StatusCode::TOO_MANY_REQUESTS, "resource_exhausted",
+                                &format!("the {} concurrency limit is reached", outcome.limit))
+                        };
+                        limited.push(outcome);
+                        return Admission { permit: Err(err), limited };
+                    },
+                }
+                limited.push(outcome);
+            }
+            Admission { permit: Ok(Box::new(permits)), limited }
+        })
+    }
+}
@@ -0,0 +1,116 @@
+use serde::de::value::MapDeserializer;
+use serde::de::{DeserializeOwned, Deserializer, Error as _, IntoDeserializer, Visitor};
+use serde::Serialize;
+use serde_json::Value;
+use crate::TwirpError;
+
+impl TwirpError {
+    /// Set the fields of the given value as meta, keeping the other values of the `meta_map`
+    ///
+    /// The value must serialize to a map or struct. Like in `meta_map`, field values that are not strings are encoded
+    /// as JSON text so the meta stays the string map of the Twirp spec, and `meta_as` decodes them again.
+    pub fn with_meta<T: Serialize>(mut self, meta: &T) -> serde_json::Result<TwirpError> {
+        let fields = match serde_json::to_value(meta)? {
+            Value::Object(fields) => fields,
+            other => return Err(serde_json::Error::custom(format!("meta must be a map or struct, got {}", other))),
+        };
+        let mut map = self.meta_map();
+        for (key, value) in fields {
+            match value {
+                Value::Null => None,
+                Value::String(value) => map.insert(key, value),
+                value => map.insert(key, value.to_string()),
+            };
+        }
+        self.meta = Some(Value::Object(map.into_iter().map(|(k, v)| (k, Value::String(v))).collect()));
+        Ok(self)
+    }
+
+    /// Decode the `meta_map` into the given type, the reverse of `with_meta`
+    ///
+    /// Fields that are not strings are decoded from their JSON text. A missing meta is decoded as an empty map.
+    pub fn meta_as<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        let meta = self.meta_map();
+        let de = MapDeserializer::new(meta.iter().map(|(key, value)| (key.as_str(), MetaValue(value))));
+        T::deserialize(de)
+    }
+}
+
+/// A deserializer for a meta string, decoding it as JSON text unless a string is asked for
+struct MetaValue<'a>(&'a str);
+
+impl MetaValue<'_> {
+    fn json(&self) -> serde_json::Result<Value> { serde_json::from_str(self.0) }
+}
+
+impl<'de> IntoDeserializer<'de, serde_json::Error> for MetaValue<'_> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self { self }
+}
+
+macro_rules! deserialize_json {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> serde_json::Result<V::Value> {
+                self.json()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for MetaValue<'_> {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> serde_json::Result<V::Value> {
+        visitor.visit_str(self.0)
+    }
+
+    deserialize_json! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_i128
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128 deserialize_f32 deserialize_f64
+        deserialize_unit deserialize_seq deserialize_map
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> serde_json::Result<V::Value> {
+        // Null values are left out of the meta, so a present value is never `None`
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, name: &'static str, visitor: V)
+            -> serde_json::Result<V::Value> {
+        self.json()?.deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V)
+            -> serde_json::Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> serde_json::Result<V::Value> {
+        self.json()?.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, name: &'static str, len: usize, visitor: V)
+            -> serde_json::Result<V::Value> {
+        self.json()?.deserialize_tuple_struct(name, len, visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, name: &'static str, fields: &'static [&'static str], visitor: V)
+            -> serde_json::Result<V::Value> {
+        self.json()?.deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, name: &'static str, variants: &'static [&'static str], visitor: V)
+            -> serde_json::Result<V::Value> {
+        // Unit variants are plain strings, the others are JSON objects
+        match self.json() {
+            Ok(value @ Value::Object(_)) => value.deserialize_enum(name, variants, visitor),
+            _ => Value::String(self.0.to_string()).deserialize_enum(name, variants, visitor),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        char str string bytes byte_buf identifier ignored_any
+    }
+}
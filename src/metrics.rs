@@ -0,0 +1,278 @@
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::fmt::Write;
+use std::future::{ready, Ready};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll};
+use hyper::{Body, Request, Response};
+use hyper::header::{self, HeaderValue};
+use hyper::service::Service;
+use crate::{LimitOutcome, RpcHooks, RpcInfo, RpcKind, RpcOutcome};
+
+/// The latency histogram buckets in seconds used by `TwirpMetrics::new`, the same as the Prometheus client defaults
+pub const DEFAULT_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// The content type of the Prometheus text exposition format
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// A built-in registry of per-method RPC metrics, rendered in the Prometheus text exposition format
+///
+/// Add it to a `HyperServer` or `HyperClient` with `with_hooks`, the same registry can be shared by both. For each of
+/// the `server` and `client` sides it collects:
+///
+/// * `twirp_<side>_requests_started_total` - counter of requests started
+/// * `twirp_<side>_requests_in_flight` - gauge of requests started but not completed
+/// * `twirp_<side>_responses_total` - counter of completed requests by Twirp error code, `ok` on success
+/// * `twirp_<side>_request_duration_seconds` - histogram of the request latencies
+/// * `twirp_server_requests_limited_total` - counter of requests that found a limit of a `ConcurrencyLimit`
+///   full, by the `twirp_limit` and whether they were `twirp_shed`, only rendered once there are any
+///
+/// All are labeled by `twirp_service` and `twirp_method`, which are empty for paths that are not Twirp routes.
+///
+/// Clients choose the paths they call, so a server only gets a series for a method once a request to it got a response
+/// other than `bad_route`. Until then its requests are counted as `UNKNOWN_METHOD` while in flight, and as started
+/// once they complete. Requests that are answered with `bad_route` or without any response at all stay there.
+#[derive(Debug)]
+pub struct TwirpMetrics {
+    buckets: Vec<f64>,
+    registry: Mutex<Registry>,
+}
+
+/// The `twirp_service` and `twirp_method` of server requests to methods that aren't known to exist
+pub const UNKNOWN_METHOD: &str = "unknown";
+
+#[derive(Debug, Default)]
+struct Registry {
+    methods: BTreeMap<MethodKey, MethodMetrics>,
+    // The server requests in flight by RPC id
+    pending: BTreeMap<u64, Pending>,
+}
+
+/// Where a server request in flight is counted
+#[derive(Debug)]
+struct Pending {
+    key: MethodKey,
+    // Whether it was counted as started already, which is only done for known methods
+    started: bool,
+    // The limits it found full, counted along with the response unless it was counted as started already
+    limited: Vec<(String, bool)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct MethodKey {
+    kind: RpcKind,
+    service: String,
+    method: String,
+}
+
+#[derive(Debug, Default)]
+struct MethodMetrics {
+    started: u64,
+    in_flight: i64,
+    responses: BTreeMap<String, u64>,
+    limited: BTreeMap<(String, bool), u64>,
+    // One count per bucket, not cumulative
+    bucket_counts: Vec<u64>,
+    latency_sum: f64,
+    latency_count: u64,
+}
+
+impl Default for TwirpMetrics {
+    fn default() -> TwirpMetrics { TwirpMetrics::new() }
+}
+
+impl TwirpMetrics {
+    /// Create a new registry using the `DEFAULT_BUCKETS`
+    pub fn new() -> TwirpMetrics { TwirpMetrics::with_buckets(DEFAULT_BUCKETS) }
+
+    /// Create a new registry with the given latency histogram bucket upper bounds in seconds
+    pub fn with_buckets(buckets: &[f64]) -> TwirpMetrics {
+        let mut buckets = buckets.to_vec();
+        buckets.sort_by(|a, b| a.partial_cmp(b).expect("histogram buckets must not be NaN"));
+        buckets.dedup();
+        TwirpMetrics { buckets, registry: Mutex::new(Registry::default()) }
+    }
+
+    /// A hyper service that responds to every request with the rendered metrics
+    pub fn handler(self: &Arc<Self>) -> MetricsHandler { MetricsHandler(self.clone()) }
+
+    fn registry(&self) -> MutexGuard<'_, Registry> { self.registry.lock().unwrap_or_else(|err| err.into_inner()) }
+
+    fn series<'a>(&self, registry: &'a mut Registry, key: MethodKey) -> &'a mut MethodMetrics {
+        registry.methods.entry(key).or_insert_with(|| MethodMetrics {
+            bucket_counts: vec![0; self.buckets.len()],
+            ..Default::default()
+        })
+    }
+
+    /// Render all metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let registry = self.registry();
+        let methods = &registry.methods;
+        let mut out = String::new();
+        for kind in &[RpcKind::Server, RpcKind::Client] {
+            let side = match kind { RpcKind::Server => "server", RpcKind::Client => "client" };
+            let entries: Vec<_> = methods.iter().filter(|(key, _)| key.kind == *kind).collect();
+            if entries.is_empty() { continue; }
+
+            let name = format!("twirp_{}_requests_started_total", side);
+            write_header(&mut out, &name, "counter", "Total number of RPCs started.");
+            for (key, m) in &entries {
+                let _ = writeln!(out, "{}{{{}}} {}", name, labels(key, &[]), m.started);
+            }
+
+            let name = format!("twirp_{}_requests_in_flight", side);
+            write_header(&mut out, &name, "gauge", "Number of RPCs started but not completed.");
+            for (key, m) in &entries {
+                let _ = writeln!(out, "{}{{{}}} {}", name, labels(key, &[]), m.in_flight);
+            }
+
+            let name = format!("twirp_{}_responses_total", side);
+            write_header(&mut out, &name, "counter", "Total number of RPCs completed, by Twirp error code.");
+            for (key, m) in &entries {
+                for (code, count) in &m.responses {
+                    let _ = writeln!(out, "{}{{{}}} {}", name, labels(key, &[("twirp_code", code)]), count);
+                }
+            }
+
+            let name = format!("twirp_{}_request_duration_seconds", side);
+            write_header(&mut out, &name, "histogram", "Latency of completed RPCs in seconds.");
+            for (key, m) in &entries {
+                let mut cumulative = 0;
+                for (bound, count) in self.buckets.iter().zip(&m.bucket_counts) {
+                    cumulative += count;
+                    let le = bound.to_string();
+                    let _ = writeln!(out, "{}_bucket{{{}}} {}", name, labels(key, &[("le", &le)]), cumulative);
+                }
+                let _ = writeln!(out, "{}_bucket{{{}}} {}", name, labels(key, &[("le", "+Inf")]), m.latency_count);
+                let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels(key, &[]), m.latency_sum);
+                let _ = writeln!(out, "{}_count{{{}}} {}", name, labels(key, &[]), m.latency_count);
+            }
+
+            if entries.iter().any(|(_, m)| !m.limited.is_empty()) {
+                let name = format!("twirp_{}_requests_limited_total", side);
+                write_header(&mut out, &name, "counter",
+                    "Total number of RPCs that found a concurrency limit full, by limit and whether they were shed.");
+                for (key, m) in &entries {
+                    for ((limit, shed), count) in &m.limited {
+                        let shed = if *shed { "true" } else { "false" };
+                        let extra = [("twirp_limit", limit.as_str()), ("twirp_shed", shed)];
+                        let _ = writeln!(out, "{}{{{}}} {}", name, labels(key, &extra), count);
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+impl RpcHooks for TwirpMetrics {
+    fn request_started(&self, rpc: &RpcInfo) {
+        let key = MethodKey::new(rpc);
+        let mut registry = self.registry();
+        let known = rpc.kind == RpcKind::Client || registry.methods.contains_key(&key);
+        let key = if known { key } else { MethodKey::unknown() };
+        let m = self.series(&mut registry, key.clone());
+        if known { m.started += 1; }
+        m.in_flight += 1;
+        if rpc.kind == RpcKind::Server {
+            registry.pending.insert(rpc.id, Pending { key, started: known, limited: Vec::new() });
+        }
+    }
+
+    fn request_completed(&self, rpc: &RpcInfo, outcome: &RpcOutcome) {
+        let latency = outcome.latency.as_secs_f64();
+        let bucket = self.buckets.iter().position(|bound| latency <= *bound);
+        let mut registry = self.registry();
+        let pending = match rpc.kind {
+            RpcKind::Client => Pending { key: MethodKey::new(rpc), started: true, limited: Vec::new() },
+            RpcKind::Server => match registry.pending.remove(&rpc.id) {
+                Some(pending) => pending,
+                None => return,
+            },
+        };
+        // Shed requests are answered before routing, so they don't tell whether the method exists either
+        let shed = pending.limited.iter().any(|(_, shed)| *shed);
+        let routed = rpc.kind == RpcKind::Client || pending.started ||
+            (outcome.status.is_some() && outcome.code() != "bad_route" && !shed);
+        let key = if routed { MethodKey::new(rpc) } else { MethodKey::unknown() };
+        self.series(&mut registry, pending.key).in_flight -= 1;
+        let m = self.series(&mut registry, key);
+        if !pending.started { m.started += 1; }
+        pending.limited.into_iter().for_each(|limit| *m.limited.entry(limit).or_insert(0) += 1);
+        *m.responses.entry(outcome.code().to_string()).or_insert(0) += 1;
+        if let Some(bucket) = bucket { m.bucket_counts[bucket] += 1; }
+        m.latency_sum += latency;
+        m.latency_count += 1;
+    }
+
+    fn request_finished(&self, rpc: &RpcInfo) {
+        // Only requests that were dropped before they completed are still pending
+        let mut registry = self.registry();
+        if let Some(pending) = registry.pending.remove(&rpc.id) {
+            let m = self.series(&mut registry, pending.key);
+            m.in_flight -= 1;
+            if !pending.started { m.started += 1; }
+            pending.limited.into_iter().for_each(|limit| *m.limited.entry(limit).or_insert(0) += 1);
+        }
+    }
+
+    fn request_limited(&self, rpc: &RpcInfo, outcome: &LimitOutcome) {
+        let limit = (outcome.limit.clone(), outcome.shed);
+        let mut registry = self.registry();
+        match registry.pending.get_mut(&rpc.id) {
+            Some(pending) if !pending.started => pending.limited.push(limit),
+            Some(pending) => {
+                let key = pending.key.clone();
+                *self.series(&mut registry, key).limited.entry(limit).or_insert(0) += 1;
+            },
+            None => (),
+        }
+    }
+}
+
+impl MethodKey {
+    fn new(rpc: &RpcInfo) -> MethodKey {
+        MethodKey { kind: rpc.kind, service: rpc.service.clone(), method: rpc.method.clone() }
+    }
+
+    fn unknown() -> MethodKey {
+        MethodKey { kind: RpcKind::Server, service: UNKNOWN_METHOD.to_string(), method: UNKNOWN_METHOD.to_string() }
+    }
+}
+
+fn write_header(out: &mut String, name: &str, metric_type: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, metric_type);
+}
+
+fn labels(key: &MethodKey, extra: &[(&str, &str)]) -> String {
+    let mut out = format!("twirp_service=\"{}\",twirp_method=\"{}\"",
+        escape_label(&key.service), escape_label(&key.method));
+    for (name, value) in extra {
+        let _ = write!(out, ",{}=\"{}\"", name, escape_label(value));
+    }
+    out
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// A hyper service rendering a `TwirpMetrics` registry, e.g. to be served on `/metrics`
+#[derive(Debug, Clone)]
+pub struct MetricsHandler(pub Arc<TwirpMetrics>);
+
+impl Service<Request<Body>> for MetricsHandler {
+    type Response = Response<Body>;
+    type Error = Infallible;
+    type Future = Ready<Result<Response<Body>, Infallible>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> { Poll::Ready(Ok(())) }
+
+    fn call(&mut self, _req: Request<Body>) -> Self::Future {
+        let mut resp = Response::new(Body::from(self.0.render()));
+        resp.headers_mut().insert(header::CONTENT_TYPE, HeaderValue::from_static(PROMETHEUS_CONTENT_TYPE));
+        ready(Ok(resp))
+    }
+}
@@ -0,0 +1,63 @@
+use std::future;
+use std::sync::Arc;
+use hyper::StatusCode;
+use crate::{HyperService, PTRes, ServiceDescriptor, ServiceRequest, TwirpError};
+
+/// A `HyperService` serving several services, routing requests by the `/twirp/<service>/` prefix of their path
+///
+/// Services are matched with their descriptor, so generated servers route on their own. The first service without a
+/// descriptor gets every request no other service matches, otherwise those are answered with a `bad_route` error.
+#[derive(Clone, Default)]
+pub struct TwirpRouter {
+    services: Vec<Arc<dyn HyperService + Send + Sync>>,
+}
+
+impl TwirpRouter {
+    /// Create a router without any services
+    pub fn new() -> TwirpRouter { TwirpRouter::default() }
+
+    /// Add a service, e.g. a generated `<Service>Server`
+    pub fn with_service<T: HyperService + Send + Sync + 'static>(mut self, service: T) -> TwirpRouter {
+        self.services.push(Arc::new(service));
+        self
+    }
+
+    /// The descriptors of the services with one, in the order they were added
+    pub fn descriptors(&self) -> Vec<&'static ServiceDescriptor> {
+        self.services.iter().filter_map(|v| v.descriptor()).collect()
+    }
+
+    fn route(&self, path: &str) -> Option<&Arc<dyn HyperService + Send + Sync>> {
+        let routed = self.services.iter().find(|service| service.descriptor().is_some_and(|descriptor| {
+            path.strip_prefix("/twirp/").and_then(|v| v.strip_prefix(descriptor.full_name)).
+                is_some_and(|v| v.starts_with('/'))
+        }));
+        routed.or_else(|| self.services.iter().find(|service| service.descriptor().is_none()))
+    }
+}
+
+impl std::fmt::Debug for TwirpRouter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<_> = self.services.iter().map(|v| v.descriptor().map_or("<no descriptor>", |v| v.full_name)).
+            collect();
+        f.debug_struct("TwirpRouter").field("services", &names).finish()
+    }
+}
+
+impl HyperService for TwirpRouter {
+    fn handle(&self, req: ServiceRequest<Vec<u8>>) -> PTRes<Vec<u8>> {
+        match self.route(req.uri.path()) {
+            Some(service) => service.handle(req),
+            None => Box::pin(future::ready(Err(TwirpError::new(StatusCode::NOT_FOUND, "bad_route",
+                &format!("no handler for path {:?}", req.uri.path())).into()))),
+        }
+    }
+
+    fn descriptor(&self) -> Option<&'static ServiceDescriptor> {
+        // Only a router of a single service stands for that service
+        match self.services.as_slice() {
+            [service] => service.descriptor(),
+            _ => None,
+        }
+    }
+}
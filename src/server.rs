@@ -0,0 +1,348 @@
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::error::Error as StdError;
+use std::future::{self, Future};
+use std::io;
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use futures_util::FutureExt;
+use hyper::{Body, Request, Response, Server};
+use hyper::server::accept::Accept;
+use hyper::server::conn::{AddrIncoming, AddrStream};
+use hyper::service::Service;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, watch, OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinHandle;
+use crate::{HyperServer, HyperService, PeerIdentity, RpcHooks, RpcInfo, TwirpRouter};
+
+type ConfigureServer = Box<dyn FnOnce(HyperServer<TwirpRouter>) -> HyperServer<TwirpRouter> + Send>;
+
+/// A builder serving one or more services on one or more addresses, see `start`
+///
+/// This takes care of what serving with hyper by hand takes: a `TwirpRouter` for the services, a `HyperServer` per
+/// connection knowing its peer, and graceful shutdown through the returned `TwirpServerHandle`.
+#[derive(Default)]
+pub struct TwirpServerBuilder {
+    router: TwirpRouter,
+    configure: Vec<ConfigureServer>,
+    addrs: Vec<SocketAddr>,
+    #[cfg(unix)]
+    unix_paths: Vec<PathBuf>,
+    #[cfg(feature = "rustls")]
+    tls: Option<rustls::ServerConfig>,
+    #[cfg(feature = "http2")]
+    http2_only: bool,
+    max_connections: Option<usize>,
+}
+
+impl TwirpServerBuilder {
+    /// Create a builder without any services or addresses
+    pub fn new() -> TwirpServerBuilder { TwirpServerBuilder::default() }
+
+    /// Serve the given service, e.g. a generated `<Service>Server`, next to the others, see `TwirpRouter`
+    pub fn with_service<T: HyperService + Send + Sync + 'static>(mut self, service: T) -> TwirpServerBuilder {
+        self.router = self.router.with_service(service);
+        self
+    }
+
+    /// Configure the `HyperServer` serving the services, e.g. to add hooks or an access log
+    pub fn with_server<F>(mut self, f: F) -> TwirpServerBuilder
+            where F: FnOnce(HyperServer<TwirpRouter>) -> HyperServer<TwirpRouter> + Send + 'static {
+        self.configure.push(Box::new(f));
+        self
+    }
+
+    /// Serve on the given TCP address, with port 0 for any free port
+    pub fn bind(mut self, addr: SocketAddr) -> TwirpServerBuilder {
+        self.addrs.push(addr);
+        self
+    }
+
+    /// Serve on a Unix socket at the given path, see `UnixIncoming::bind`
+    #[cfg(unix)]
+    pub fn bind_unix(mut self, path: impl Into<PathBuf>) -> TwirpServerBuilder {
+        self.unix_paths.push(path.into());
+        self
+    }
+
+    /// Serve TCP addresses over TLS with the given config, see `tls_server_config`
+    ///
+    /// The identity of clients presenting a certificate is set on the server of their connection, see
+    /// `HyperServer::with_peer_identity`.
+    #[cfg(feature = "rustls")]
+    pub fn with_tls(mut self, config: rustls::ServerConfig) -> TwirpServerBuilder {
+        self.tls = Some(config);
+        self
+    }
+
+    /// Set whether to only serve prior knowledge HTTP/2 instead of both HTTP/1.1 and HTTP/2
+    #[cfg(feature = "http2")]
+    pub fn with_http2_only(mut self, http2_only: bool) -> TwirpServerBuilder {
+        self.http2_only = http2_only;
+        self
+    }
+
+    /// Stop accepting connections while the given number of connections are open, across all addresses
+    pub fn with_max_connections(mut self, max_connections: usize) -> TwirpServerBuilder {
+        self.max_connections = Some(max_connections);
+        self
+    }
+
+    /// Bind every address and start serving them in the background, which must be done inside of a tokio runtime
+    pub async fn start(self) -> io::Result<TwirpServerHandle> {
+        let in_flight = Arc::new(InFlight::default());
+        let hooks: Arc<dyn RpcHooks> = in_flight.clone();
+        let server = self.configure.into_iter().fold(HyperServer::new(self.router), |server, f| f(server)).
+            with_hooks(hooks);
+        let (shutdown, shutdown_rx) = watch::channel(false);
+        let (abort, abort_rx) = watch::channel(false);
+        let (alive, alive_rx) = mpsc::channel(1);
+        let mut handle = TwirpServerHandle {
+            local_addrs: Vec::new(),
+            servers: Vec::new(),
+            shutdown,
+            abort,
+            alive: alive_rx,
+            in_flight,
+        };
+        let start = Start {
+            make: MakeServer {
+                server,
+                connections: self.max_connections.map(|v| Arc::new(Semaphore::new(v))),
+                acquiring: None,
+                permit: None,
+            },
+            exec: ConnectionExec { abort: abort_rx, _alive: alive },
+            shutdown: shutdown_rx,
+            #[cfg(feature = "http2")]
+            http2_only: self.http2_only,
+        };
+        // Everything is bound before anything is served, so failing to bind doesn't leave servers running
+        let mut tcp = Vec::with_capacity(self.addrs.len());
+        for addr in &self.addrs {
+            tcp.push(AddrIncoming::from_listener(TcpListener::bind(addr).await?).map_err(io::Error::other)?);
+        }
+        #[cfg(unix)]
+        let mut unix = Vec::with_capacity(self.unix_paths.len());
+        #[cfg(unix)]
+        for path in &self.unix_paths {
+            unix.push(crate::UnixIncoming::bind(path).await?);
+        }
+        for incoming in tcp {
+            handle.local_addrs.push(incoming.local_addr());
+            #[cfg(feature = "rustls")]
+            if let Some(config) = &self.tls {
+                handle.servers.push(start.spawn(crate::TlsIncoming::new(incoming, config.clone())));
+                continue;
+            }
+            handle.servers.push(start.spawn(incoming));
+        }
+        #[cfg(unix)]
+        for incoming in unix {
+            handle.servers.push(start.spawn(incoming));
+        }
+        Ok(handle)
+    }
+}
+
+impl std::fmt::Debug for TwirpServerBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TwirpServerBuilder").field("router", &self.router).field("addrs", &self.addrs).
+            field("max_connections", &self.max_connections).finish()
+    }
+}
+
+/// A handle to the servers started by `TwirpServerBuilder::start`
+///
+/// Dropping the handle leaves the servers running in the background.
+pub struct TwirpServerHandle {
+    local_addrs: Vec<SocketAddr>,
+    servers: Vec<JoinHandle<()>>,
+    shutdown: watch::Sender<bool>,
+    abort: watch::Sender<bool>,
+    alive: mpsc::Receiver<()>,
+    in_flight: Arc<InFlight>,
+}
+
+impl TwirpServerHandle {
+    /// The addresses TCP connections are accepted on, in the order they were bound
+    pub fn local_addrs(&self) -> &[SocketAddr] { &self.local_addrs }
+
+    /// Stop accepting connections and wait for the requests in flight to finish
+    ///
+    /// Connections still open after the timeout are closed, dropping their requests. Those aborted RPCs are returned.
+    pub async fn shutdown(mut self, timeout: Duration) -> Vec<RpcInfo> {
+        let _ = self.shutdown.send(true);
+        let drained = futures_util::future::join_all(self.servers.iter_mut());
+        let mut aborted = Vec::new();
+        if tokio::time::timeout(timeout, drained).await.is_err() {
+            // Requests are finished as their connections are dropped, so this has to be taken first
+            aborted = self.in_flight.rpcs();
+            let _ = self.abort.send(true);
+            self.servers.iter().for_each(JoinHandle::abort);
+        }
+        // Every connection task holds a sender, so this waits until they're all gone
+        drop(self.servers);
+        let _ = self.alive.recv().await;
+        aborted
+    }
+}
+
+impl std::fmt::Debug for TwirpServerHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TwirpServerHandle").field("local_addrs", &self.local_addrs).finish()
+    }
+}
+
+/// The hooks keeping track of the RPCs in flight by their id, whatever is left when connections are aborted was aborted
+#[derive(Default)]
+struct InFlight {
+    rpcs: Mutex<BTreeMap<u64, RpcInfo>>,
+}
+
+impl InFlight {
+    fn rpcs(&self) -> Vec<RpcInfo> {
+        self.rpcs.lock().map(|v| v.values().cloned().collect()).unwrap_or_default()
+    }
+}
+
+impl RpcHooks for InFlight {
+    fn request_started(&self, rpc: &RpcInfo) {
+        if let Ok(mut rpcs) = self.rpcs.lock() { rpcs.insert(rpc.id, rpc.clone()); }
+    }
+
+    fn request_finished(&self, rpc: &RpcInfo) {
+        if let Ok(mut rpcs) = self.rpcs.lock() { rpcs.remove(&rpc.id); }
+    }
+}
+
+/// What every server started by a builder shares
+struct Start {
+    make: MakeServer,
+    exec: ConnectionExec,
+    shutdown: watch::Receiver<bool>,
+    #[cfg(feature = "http2")]
+    http2_only: bool,
+}
+
+impl Start {
+    fn spawn<I>(&self, incoming: I) -> JoinHandle<()>
+            where I: Accept + Send + 'static, I::Conn: Peer + AsyncRead + AsyncWrite + Unpin + Send + 'static,
+                I::Error: Into<Box<dyn StdError + Send + Sync>> {
+        let builder = Server::builder(incoming).executor(self.exec.clone());
+        #[cfg(feature = "http2")]
+        let builder = builder.http2_only(self.http2_only);
+        let signal = signalled(self.shutdown.clone());
+        let server = builder.serve(self.make.clone()).with_graceful_shutdown(signal);
+        tokio::spawn(server.map(|_| ()))
+    }
+}
+
+/// Resolves once `true` is sent, never if the sender is dropped first
+async fn signalled(mut rx: watch::Receiver<bool>) {
+    while !*rx.borrow() {
+        if rx.changed().await.is_err() {
+            future::pending::<()>().await;
+        }
+    }
+}
+
+/// Spawns the connections of a server, dropping them when aborted
+#[derive(Clone)]
+struct ConnectionExec {
+    abort: watch::Receiver<bool>,
+    _alive: mpsc::Sender<()>,
+}
+
+impl<F: Future<Output=()> + Send + 'static> hyper::rt::Executor<F> for ConnectionExec {
+    fn execute(&self, fut: F) {
+        let exec = self.clone();
+        tokio::spawn(async move {
+            futures_util::future::select(Box::pin(fut), Box::pin(signalled(exec.abort.clone()))).await;
+            drop(exec);
+        });
+    }
+}
+
+/// Makes the `HyperServer` of every connection, waiting for a connection to close when at the maximum
+struct MakeServer {
+    server: HyperServer<TwirpRouter>,
+    connections: Option<Arc<Semaphore>>,
+    acquiring: Option<Pin<Box<dyn Future<Output=OwnedSemaphorePermit> + Send>>>,
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Clone for MakeServer {
+    fn clone(&self) -> MakeServer {
+        MakeServer { server: self.server.clone(), connections: self.connections.clone(), acquiring: None, permit: None }
+    }
+}
+
+impl<'a, C: Peer> Service<&'a C> for MakeServer {
+    type Response = ConnectionServer;
+    type Error = Infallible;
+    type Future = future::Ready<Result<ConnectionServer, Infallible>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        // hyper only accepts a connection once this is ready
+        if let (Some(connections), None) = (&self.connections, &self.permit) {
+            let connections = connections.clone();
+            let acquiring = self.acquiring.get_or_insert_with(|| {
+                Box::pin(connections.acquire_owned().map(|v| v.expect("the semaphore is never closed")))
+            });
+            let permit = futures_util::ready!(acquiring.as_mut().poll(cx));
+            self.acquiring = None;
+            self.permit = Some(permit);
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, conn: &'a C) -> Self::Future {
+        let (addr, peer_identity) = conn.peer();
+        let mut server = self.server.clone();
+        if let Some(addr) = addr { server = server.with_peer(addr); }
+        if let Some(peer_identity) = peer_identity { server = server.with_peer_identity(peer_identity); }
+        future::ready(Ok(ConnectionServer { server, _permit: self.permit.take() }))
+    }
+}
+
+/// The server of a single connection, holding its spot among the maximum connections
+struct ConnectionServer {
+    server: HyperServer<TwirpRouter>,
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Service<Request<Body>> for ConnectionServer {
+    type Response = Response<Body>;
+    type Error = hyper::Error;
+    type Future = <HyperServer<TwirpRouter> as Service<Request<Body>>>::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), hyper::Error>> { self.server.poll_ready(cx) }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future { self.server.call(req) }
+}
+
+/// What is known about the peer of an accepted connection
+trait Peer {
+    fn peer(&self) -> (Option<SocketAddr>, Option<PeerIdentity>);
+}
+
+impl Peer for AddrStream {
+    fn peer(&self) -> (Option<SocketAddr>, Option<PeerIdentity>) { (Some(self.remote_addr()), None) }
+}
+
+#[cfg(feature = "rustls")]
+impl Peer for crate::TlsConn {
+    fn peer(&self) -> (Option<SocketAddr>, Option<PeerIdentity>) { (Some(self.remote_addr()), self.peer_identity()) }
+}
+
+#[cfg(unix)]
+impl Peer for tokio::net::UnixStream {
+    fn peer(&self) -> (Option<SocketAddr>, Option<PeerIdentity>) { (None, None) }
+}
@@ -1,133 +1,378 @@
-use prost_build::{Method, Service, ServiceGenerator};
+use prost_build::{Comments, Method, Service, ServiceGenerator};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Ident, Type};
 
 #[derive(Default)]
 pub struct TwirpServiceGenerator {
     pub embed_client: bool,
-    type_aliases_generated: bool,
+    /// Also generate tonic's gRPC server and client for every service, and adapters between them and the Twirp trait
+    ///
+    /// Needs the `prost_twirp` crate with the `tonic` feature at runtime, it can't be used with `embed_client`.
+    #[cfg(feature = "tonic-gen")]
+    pub tonic: bool,
 }
 
 impl TwirpServiceGenerator {
     pub fn new() -> TwirpServiceGenerator { Default::default() }
 
-    fn prost_twirp_mod(&self) -> &str { if self.embed_client { "prost_twirp" } else { "::prost_twirp" } }
+    fn prost_twirp_mod(&self) -> TokenStream {
+        if self.embed_client { quote!(prost_twirp) } else { quote!(::prost_twirp) }
+    }
+
+    fn generate_type_aliases(&self) -> TokenStream {
+        let pt = self.prost_twirp_mod();
+        quote! {
+            pub type PTReq<I> = #pt::PTReq<I>;
+            pub type PTRes<O> = #pt::PTRes<O>;
+        }
+    }
+
+    fn generate_embedded_runtime(&self) -> TokenStream {
+        let runtime: syn::File = syn::parse_str(include_str!("service_run.rs"))
+            .expect("embedded runtime source is valid Rust");
+        let items = runtime.items;
+        quote! {
+            /// Embedded module from prost_twirp source
+            // The runtime's optional features refer to features of the crate including the generated code
+            #[allow(dead_code, unexpected_cfgs)]
+            mod prost_twirp {
+                #(#items)*
+            }
+        }
+    }
+
+    fn service_full_name(service: &Service) -> String {
+        if service.package.is_empty() {
+            service.proto_name.clone()
+        } else {
+            format!("{}.{}", service.package, service.proto_name)
+        }
+    }
+
+    fn method_path(service: &Service, method: &Method) -> String {
+        format!("/twirp/{}/{}", Self::service_full_name(service), method.proto_name)
+    }
+
+    fn method_ident(method: &Method) -> Ident {
+        // Keyword method names come in already escaped by prost (e.g. `r#type`), which `syn` parses as raw idents
+        syn::parse_str(&method.name).unwrap_or_else(|err|
+            panic!("method `{}` has an invalid Rust name `{}`: {}", method.proto_name, method.name, err))
+    }
+
+    fn rust_type(method: &Method, rust_type: &str) -> Type {
+        syn::parse_str(rust_type).unwrap_or_else(|err|
+            panic!("method `{}` has an invalid Rust type `{}`: {}", method.proto_name, rust_type, err))
+    }
+
+    /// Doc attributes for the attached comments, laid out like prost's `Comments::append_with_indent`
+    ///
+    /// Detached comments are not part of the token stream, see `detached_comments`.
+    fn doc_attrs(comments: &Comments) -> TokenStream {
+        let mut lines: Vec<&str> = comments.leading.iter().map(String::as_str).collect();
+        if !comments.leading.is_empty() && !comments.trailing.is_empty() { lines.push(""); }
+        lines.extend(comments.trailing.iter().map(String::as_str));
+        quote! { #(#[doc = #lines])* }
+    }
+
+    /// Plain `//` lines for the detached comments, which have no token representation
+    fn detached_comments(comments: &Comments) -> String {
+        comments.leading_detached.iter().map(|block| {
+            block.iter().map(|line| format!("//{}\n", line)).collect::<String>() + "\n"
+        }).collect()
+    }
+
+    fn generate_main_trait(&self, service: &Service) -> TokenStream {
+        let docs = Self::doc_attrs(&service.comments);
+        let name = format_ident!("{}", service.name);
+        let methods = service.methods.iter().map(|method| {
+            let docs = Self::doc_attrs(&method.comments);
+            let sig = self.method_sig(method);
+            quote! {
+                #docs
+                #sig;
+            }
+        });
+        quote! {
+            #docs
+            pub trait #name {
+                #(#methods)*
+            }
+        }
+    }
+
+    fn method_sig(&self, method: &Method) -> TokenStream {
+        let pt = self.prost_twirp_mod();
+        let name = Self::method_ident(method);
+        let input_type = Self::rust_type(method, &method.input_type);
+        let output_type = Self::rust_type(method, &method.output_type);
+        quote! { fn #name(&self, i: #pt::PTReq<#input_type>) -> #pt::PTRes<#output_type> }
+    }
 
-    fn generate_type_aliases(&mut self, buf: &mut String) {
-        if !self.type_aliases_generated {
-            self.type_aliases_generated = true;
-            buf.push_str(&format!(
-                "\n\
-                pub type PTReq<I> = {0}::PTReq<I>;\n\
-                pub type PTRes<O> = {0}::PTRes<O>;\n",
-                self.prost_twirp_mod()));
+    fn generate_main_impl(&self, service: &Service) -> TokenStream {
+        let pt = self.prost_twirp_mod();
+        let name = format_ident!("{}", service.name);
+        let client_name = format_ident!("{}Client", service.name);
+        let server_name = format_ident!("{}Server", service.name);
+        let full_name = Self::service_full_name(service);
+        let path_prefix = format!("/twirp/{}/", full_name);
+        let package = &service.package;
+        let proto_name = &service.proto_name;
+        let method_descriptors = self.method_descriptors(service);
+        quote! {
+            // Not every crate uses every constant and constructor
+            #[allow(dead_code)]
+            impl dyn #name {
+                /// The fully-qualified protobuf name of the service, without a leading dot
+                pub const FULLY_QUALIFIED_NAME: &'static str = #full_name;
+                /// The prefix of every route of the service, including the trailing slash
+                pub const PATH_PREFIX: &'static str = #path_prefix;
+                /// The static descriptor of the service and its methods
+                pub const DESCRIPTOR: #pt::ServiceDescriptor = #pt::ServiceDescriptor {
+                    package: #package,
+                    name: #proto_name,
+                    full_name: #full_name,
+                    methods: &[#(#method_descriptors),*],
+                };
+
+                pub fn new_client(
+                    client: ::hyper::client::Client<::hyper::client::HttpConnector, ::hyper::body::Body>,
+                    root_url: &str,
+                ) -> Box<dyn #name> {
+                    Box::new(#client_name(#pt::HyperClient::new(client, root_url)))
+                }
+
+                pub fn new_server<T: Send + Sync + 'static + #name>(v: T) -> Box<dyn ::hyper::service::Service<
+                    ::hyper::Request<::hyper::body::Body>,
+                    Response = ::hyper::Response<::hyper::body::Body>,
+                    Error = ::hyper::Error,
+                    Future = ::std::pin::Pin<Box<dyn ::std::future::Future<
+                        Output = ::std::result::Result<::hyper::Response<::hyper::body::Body>, ::hyper::Error>
+                    > + Send>>,
+                > + Send> {
+                    Box::new(#pt::HyperServer::new(#server_name(::std::sync::Arc::new(v))))
+                }
+            }
         }
     }
 
-    fn generate_main_trait(&self, service: &Service, buf: &mut String) {
-        buf.push_str("\n");
-        service.comments.append_with_indent(0, buf);
-        buf.push_str(&format!("pub trait {} {{", service.name));
-        for method in service.methods.iter() {
-            buf.push_str("\n");
-            method.comments.append_with_indent(1, buf);
-            buf.push_str(&format!("    {};\n", self.method_sig(method)));
+    fn method_descriptors(&self, service: &Service) -> Vec<TokenStream> {
+        let pt = self.prost_twirp_mod();
+        service.methods.iter().map(|method| {
+            let name = method.name.trim_start_matches("r#");
+            let proto_name = &method.proto_name;
+            let path = Self::method_path(service, method);
+            let input_type = method.input_proto_type.trim_start_matches('.');
+            let output_type = method.output_proto_type.trim_start_matches('.');
+            quote! {
+                #pt::MethodDescriptor {
+                    name: #name,
+                    proto_name: #proto_name,
+                    path: #path,
+                    input_type: #input_type,
+                    output_type: #output_type,
+                }
+            }
+        }).collect()
+    }
+
+    fn generate_client_struct(&self, service: &Service) -> TokenStream {
+        let pt = self.prost_twirp_mod();
+        let client_name = format_ident!("{}Client", service.name);
+        quote! {
+            pub struct #client_name<C = ::hyper::client::HttpConnector>(pub #pt::HyperClient<C>);
         }
-        buf.push_str("}\n");
-    }
-
-    fn method_sig(&self, method: &Method) -> String {
-        format!("fn {0}(&self, i: {1}::PTReq<{2}>) -> {1}::PTRes<{3}>",
-            method.name, self.prost_twirp_mod(), method.input_type, method.output_type)
-    }
-
-    fn generate_main_impl(&self, service: &Service, buf: &mut String) {
-        buf.push_str(&format!(
-            "\n\
-            impl dyn {0} {{\n    \
-                pub fn new_client(client: ::hyper::client::Client<::hyper::client::HttpConnector, ::hyper::body::Body>, root_url: &str) -> Box<dyn {0}> {{\n        \
-                    Box::new({0}Client({1}::HyperClient::new(client, root_url)))\n    \
-                }}\n    \
-                pub fn new_server<T: Send + Sync + 'static + {0}>(v: T) -> Box<dyn ::hyper::service::Service<::hyper::Request<::hyper::body::Body>,\n            \
-                        Response=::hyper::Response<::hyper::body::Body>,\n \
-                        Error=::hyper::Error,\n \
-                        Future=::std::pin::Pin<Box<dyn ::std::future::Future<\n \
-                            Output=::std::result::Result<\n \
-                                ::hyper::Response<::hyper::body::Body>,\n \
-                                ::hyper::Error>>+Send>>>+Send> {{\n        \
-                    Box::new({1}::HyperServer::new({0}Server(::std::sync::Arc::new(v))))\n    \
-                }}\n\
-            }}\n",
-            service.name, self.prost_twirp_mod()));
-    }
-
-    fn generate_client_struct(&self, service: &Service, buf: &mut String) {
-        buf.push_str(&format!(
-            "\npub struct {}Client(pub {}::HyperClient);\n",
-            service.name, self.prost_twirp_mod()));
-    }
-
-    fn generate_client_impl(&self, service: &Service, buf: &mut String) {
-        buf.push_str(&format!("\nimpl {0} for {0}Client {{", service.name));
-        for method in service.methods.iter() {
-            buf.push_str(&format!(
-                "\n    {} {{\n        \
-                    self.0.go(\"/twirp/{}.{}/{}\", i)\n    \
-                }}\n", self.method_sig(method), service.package, service.proto_name, method.proto_name));
+    }
+
+    fn generate_client_impl(&self, service: &Service) -> TokenStream {
+        let name = format_ident!("{}", service.name);
+        let client_name = format_ident!("{}Client", service.name);
+        let methods = service.methods.iter().map(|method| {
+            let sig = self.method_sig(method);
+            let path = Self::method_path(service, method);
+            quote! {
+                #sig {
+                    self.0.go(#path, i)
+                }
+            }
+        });
+        quote! {
+            impl<C: ::hyper::client::connect::Connect + Clone + Send + Sync + 'static> #name for #client_name<C> {
+                #(#methods)*
+            }
         }
-        buf.push_str("}\n");
     }
 
-    fn generate_server_struct(&self, service: &Service, buf: &mut String) {
-        buf.push_str(&format!(
-            "\npub struct {0}Server<T: 'static + {0}>(::std::sync::Arc<T>);\n",
-            service.name));
+    fn generate_server_struct(&self, service: &Service) -> TokenStream {
+        let name = format_ident!("{}", service.name);
+        let server_name = format_ident!("{}Server", service.name);
+        quote! {
+            pub struct #server_name<T: 'static + #name>(pub ::std::sync::Arc<T>);
+        }
     }
 
-    fn generate_server_impl(&self, service: &Service, buf: &mut String) {
-        buf.push_str(&format!(
-            "\n\
-            impl<T: Send + Sync + 'static + {0}> {1}::HyperService for {0}Server<T> {{\n    \
-                fn handle(&self, req: {1}::ServiceRequest<Vec<u8>>) -> {1}::PTRes<Vec<u8>> {{\n        \
-                    use ::futures::{{FutureExt, TryFutureExt}};\n        \
-                    let static_service = self.0.clone();\n        \
-                    match (req.method.clone(), req.uri.path()) {{",
-            service.name, self.prost_twirp_mod()));
+    fn generate_server_impl(&self, service: &Service) -> TokenStream {
+        let pt = self.prost_twirp_mod();
+        let name = format_ident!("{}", service.name);
+        let server_name = format_ident!("{}Server", service.name);
         // Make match arms for each type
-        for method in service.methods.iter() {
-            buf.push_str(&format!(
-                "\n            \
-                (::hyper::Method::POST, \"/twirp/{}.{}/{}\") =>\n                \
-                    Box::pin(::std::future::ready(req.to_proto()).and_then(move |v| static_service.{}(v).map(|r| r.and_then(|v| v.to_proto_raw())))),",
-                service.package, service.proto_name, method.proto_name, method.name));
+        let arms = service.methods.iter().map(|method| {
+            let path = Self::method_path(service, method);
+            let method_name = Self::method_ident(method);
+            quote! {
+                (::hyper::Method::POST, #path) => Box::pin(
+                    ::std::future::ready(req.to_proto())
+                        .and_then(move |v| static_service.#method_name(v).map(|r| r.and_then(|v| v.to_proto_raw())))
+                ),
+            }
+        });
+        quote! {
+            impl<T: Send + Sync + 'static + #name> #pt::HyperService for #server_name<T> {
+                fn handle(&self, req: #pt::ServiceRequest<Vec<u8>>) -> #pt::PTRes<Vec<u8>> {
+                    use ::futures::{FutureExt, TryFutureExt};
+                    let static_service = self.0.clone();
+                    match (req.method.clone(), req.uri.path()) {
+                        #(#arms)*
+                        (_, path) => Box::pin(::std::future::ready(Err(#pt::TwirpError::new(
+                            ::hyper::StatusCode::NOT_FOUND, "bad_route", &format!("no handler for path {:?}", path),
+                        ).into()))),
+                    }
+                }
+
+                fn descriptor(&self) -> Option<&'static #pt::ServiceDescriptor> {
+                    Some(&<dyn #name>::DESCRIPTOR)
+                }
+            }
+        }
+    }
+
+    /// tonic's gRPC server and client modules, and the adapters between them and the Twirp trait
+    ///
+    /// `<Name>Grpc` implements tonic's server trait with a Twirp implementation and `<Name>GrpcClient` implements the
+    /// Twirp trait with tonic's client.
+    #[cfg(feature = "tonic-gen")]
+    fn generate_tonic(&self, service: &Service) -> TokenStream {
+        assert!(!self.embed_client, "tonic adapters need the prost_twirp crate, they can't use the embedded runtime");
+        if let Some(method) = service.methods.iter().find(|m| m.client_streaming || m.server_streaming) {
+            panic!("method `{}` is streaming, which tonic adapters don't support", method.proto_name);
+        }
+        let attributes = tonic_build::Attributes::default();
+        let tonic_server = tonic_build::server::generate(service, true, "super", false, &attributes);
+        let tonic_client = tonic_build::client::generate(service, true, "super", false, &attributes);
+
+        let name = format_ident!("{}", service.name);
+        let server_mod = format_ident!("{}_server", Self::snake_case(&service.name));
+        let client_mod = format_ident!("{}_client", Self::snake_case(&service.name));
+        let tonic_server_name = format_ident!("{}Server", service.name);
+        let tonic_client_name = format_ident!("{}Client", service.name);
+        let grpc_name = format_ident!("{}Grpc", service.name);
+        let grpc_client_name = format_ident!("{}GrpcClient", service.name);
+        let server_methods = service.methods.iter().map(|method| {
+            let path = Self::method_path(service, method);
+            let method_name = Self::method_ident(method);
+            let input_type = Self::rust_type(method, &method.input_type);
+            let output_type = Self::rust_type(method, &method.output_type);
+            quote! {
+                async fn #method_name(&self, request: ::tonic::Request<#input_type>)
+                        -> ::std::result::Result<::tonic::Response<#output_type>, ::tonic::Status> {
+                    let req = ::prost_twirp::ServiceRequest::from_tonic(#path, request);
+                    Ok(#name::#method_name(&*self.0, req).await?.into_tonic())
+                }
+            }
+        });
+        let client_methods = service.methods.iter().map(|method| {
+            let sig = self.method_sig(method);
+            let method_name = Self::method_ident(method);
+            quote! {
+                #sig {
+                    let mut client = self.0.clone();
+                    Box::pin(async move {
+                        let resp = client.#method_name(i.into_tonic()).await?;
+                        Ok(::prost_twirp::ServiceResponse::from_tonic(resp))
+                    })
+                }
+            }
+        });
+        quote! {
+            #tonic_server
+            #tonic_client
+
+            /// Serves an implementation of the Twirp trait with tonic's gRPC server
+            pub struct #grpc_name<T>(pub ::std::sync::Arc<T>);
+
+            #[::tonic::async_trait]
+            impl<T: #name + Send + Sync + 'static> #server_mod::#name for #grpc_name<T> {
+                #(#server_methods)*
+            }
+
+            /// Calls a gRPC server with tonic's client through the Twirp trait
+            pub struct #grpc_client_name(pub #client_mod::#tonic_client_name<::tonic::transport::Channel>);
+
+            impl #name for #grpc_client_name {
+                #(#client_methods)*
+            }
+
+            #[allow(dead_code)]
+            impl dyn #name {
+                /// Create a tonic gRPC server for the implementation of the Twirp trait
+                pub fn new_grpc_server<T: #name + Send + Sync + 'static>(v: T)
+                        -> #server_mod::#tonic_server_name<#grpc_name<T>> {
+                    #server_mod::#tonic_server_name::new(#grpc_name(::std::sync::Arc::new(v)))
+                }
+
+                /// Create a Twirp trait client calling a gRPC server over the channel
+                pub fn new_grpc_client(channel: ::tonic::transport::Channel) -> Box<dyn #name> {
+                    Box::new(#grpc_client_name(#client_mod::#tonic_client_name::new(channel)))
+                }
+            }
+        }
+    }
+
+    /// The snake case module name tonic uses for the service name, e.g. `haberdasher_server`
+    #[cfg(feature = "tonic-gen")]
+    fn snake_case(name: &str) -> String {
+        let mut snake = String::new();
+        let mut chars = name.chars().peekable();
+        while let Some(c) = chars.next() {
+            snake.push(c.to_ascii_lowercase());
+            if chars.peek().is_some_and(|next| next.is_uppercase()) { snake.push('_'); }
         }
-        // Final 404 arm and end fn
-        buf.push_str(&format!(
-            "\n            \
-                        _ => Box::pin(::std::future::ready(Ok({0}::TwirpError::new(::hyper::StatusCode::NOT_FOUND, \"not_found\", \"Not found\").to_resp_raw())))\n        \
-                    }}\n    \
-                }}\n\
-            }}",
-            self.prost_twirp_mod()));
+        snake
+    }
+
+    /// Format the tokens as a file
+    fn format_tokens(tokens: TokenStream) -> String {
+        let file: syn::File = syn::parse2(tokens).expect("generated code is valid Rust");
+        prettyplease::unparse(&file)
     }
 }
 
 impl ServiceGenerator for TwirpServiceGenerator {
     fn generate(&mut self, service: Service, buf: &mut String) {
-        self.generate_type_aliases(buf);
-        self.generate_main_trait(&service, buf);
-        self.generate_main_impl(&service, buf);
-        self.generate_client_struct(&service, buf);
-        self.generate_client_impl(&service, buf);
-        self.generate_server_struct(&service, buf);
-        self.generate_server_impl(&service, buf);
+        #[allow(unused_mut)]
+        let mut tokens = vec![
+            self.generate_main_trait(&service),
+            self.generate_main_impl(&service),
+            self.generate_client_struct(&service),
+            self.generate_client_impl(&service),
+            self.generate_server_struct(&service),
+            self.generate_server_impl(&service),
+        ];
+        #[cfg(feature = "tonic-gen")]
+        if self.tonic { tokens.push(self.generate_tonic(&service)); }
+        buf.push('\n');
+        buf.push_str(&Self::detached_comments(&service.comments));
+        buf.push_str(&Self::format_tokens(tokens.into_iter().collect()));
     }
 
-    fn finalize(&mut self, buf: &mut String) {
+    // Called once per output file (i.e. per package) instead of once per proto file, so that every package module gets
+    // exactly one copy of the aliases and the embedded runtime no matter how many files or packages are compiled
+    fn finalize_package(&mut self, _package: &str, buf: &mut String) {
+        buf.push('\n');
+        buf.push_str(&Self::format_tokens(self.generate_type_aliases()));
         if self.embed_client {
-            buf.push_str("\n/// Embedded module from prost_twirp source\n#[allow(dead_code)]\nmod prost_twirp {\n");
-            for line in include_str!("service_run.rs").lines() {
-                buf.push_str(&format!("    {}\n", line));
-            }
-            buf.push_str("\n}\n");
+            buf.push('\n');
+            buf.push_str(&Self::format_tokens(self.generate_embedded_runtime()));
         }
     }
 }
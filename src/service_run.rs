@@ -2,18 +2,25 @@ use std::future::Future;
 use std::future;
 use std::task::{Poll, Context};
 use std::pin::Pin;
-use hyper;
 use hyper::{body, header, Method, StatusCode, Uri, Version};
-use hyper::body::Body;
+use hyper::body::{Body, HttpBody};
 use hyper::client::{Client, HttpConnector};
+use hyper::client::connect::Connect;
 use hyper::service::Service;
-use hyper::header::{HeaderMap, HeaderValue};
+use hyper::header::{HeaderMap, HeaderName, HeaderValue};
 use prost::{DecodeError, EncodeError, Message};
-use serde_json;
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::error::Error as StdError;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant, SystemTime};
 use http::uri::InvalidUri;
 
-use futures_util::{FutureExt, TryFutureExt};
+use futures_util::FutureExt;
 
 type Request = hyper::Request<Body>;
 type Response = hyper::Response<Body>;
@@ -26,6 +33,28 @@ pub type PTReq<I> = ServiceRequest<I>;
 /// The type of every service response
 pub type PTRes<O> = Pin<Box<dyn Future<Output=Result<ServiceResponse<O>, ProstTwirpError>>+Send>>;
 
+/// The protobuf media types accepted from peers, the first being the one sent
+pub const PROTOBUF_CONTENT_TYPES: &[&str] = &["application/protobuf", "application/x-protobuf"];
+
+/// The media type of a `Content-Type` value, lowercased and without parameters
+///
+/// E.g. `Application/Protobuf; charset=binary` is `application/protobuf`. Values that aren't a `type/subtype` are
+/// `None`.
+pub fn media_type(content_type: &HeaderValue) -> Option<String> {
+    let essence = content_type.to_str().ok()?.split(';').next()?.trim();
+    let (kind, subtype) = essence.split_once('/')?;
+    let is_token = |v: &str| !v.is_empty() && !v.contains(|c: char| c.is_whitespace() || c == '/');
+    if is_token(kind) && is_token(subtype) { Some(essence.to_ascii_lowercase()) } else { None }
+}
+
+/// Whether the `Content-Type` value has one of the `PROTOBUF_CONTENT_TYPES`
+pub fn is_protobuf(content_type: &HeaderValue) -> bool {
+    media_type(content_type).is_some_and(|v| PROTOBUF_CONTENT_TYPES.contains(&v.as_str()))
+}
+
+/// The media type of protobuf bodies in the Connect protocol
+pub const CONNECT_CONTENT_TYPE: &str = "application/proto";
+
 /// A request with HTTP info and the serialized input object
 #[derive(Debug)]
 pub struct ServiceRequest<T> {
@@ -41,10 +70,21 @@ pub struct ServiceRequest<T> {
     ///
     /// Should always at least have `Content-Type`. Clients will override `Content-Length` on serialization.
     pub headers: HeaderMap,
+    /// The identity the client proved on a mutual TLS connection
+    ///
+    /// Only set by servers, see `HyperServer::with_peer_identity`.
+    pub peer_identity: Option<Arc<PeerIdentity>>,
     // The serialized request object
     pub input: T,
 }
 
+/// The identity a client proved with its certificate on a mutual TLS connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerIdentity {
+    /// The DER-encoded certificate chain the client presented, starting with its own certificate
+    pub certificates: Vec<Vec<u8>>,
+}
+
 impl<T> ServiceRequest<T> {
     /// Create new service request with the given input object
     /// 
@@ -56,7 +96,8 @@ impl<T> ServiceRequest<T> {
             uri: Default::default(),
             method: Method::POST,
             version: Version::default(),
-            headers: headers,
+            headers,
+            peer_identity: None,
             input
         }
     }
@@ -64,7 +105,7 @@ impl<T> ServiceRequest<T> {
     /// Copy this request with a different input value
     pub fn clone_with_input<U>(&self, input: U) -> ServiceRequest<U> {
         ServiceRequest { uri: self.uri.clone(), method: self.method.clone(), version: self.version,
-            headers: self.headers.clone(), input }
+            headers: self.headers.clone(), peer_identity: self.peer_identity.clone(), input }
     }
 }
 
@@ -72,26 +113,37 @@ impl<T: Message + Default + 'static> From<T> for ServiceRequest<T> {
     fn from(v: T) -> ServiceRequest<T> { ServiceRequest::new(v) }
 }
 
+#[allow(clippy::result_large_err)]
 impl ServiceRequest<Vec<u8>> {
     /// Turn a hyper request to a boxed future of a byte-array service request
     pub fn from_hyper_raw(req: Request) -> FutReq<Vec<u8>> {
+        ServiceRequest::from_hyper_raw_max(req, None)
+    }
+
+    /// Like `from_hyper_raw`, but failing with a `malformed` error instead of reading a body larger than `max_size`
+    pub fn from_hyper_raw_limited(req: Request, max_size: usize) -> FutReq<Vec<u8>> {
+        ServiceRequest::from_hyper_raw_max(req, Some(max_size))
+    }
+
+    fn from_hyper_raw_max(req: Request, max_size: Option<usize>) -> FutReq<Vec<u8>> {
         let uri = req.uri().clone();
         let method = req.method().clone();
         let version = req.version();
         let headers = req.headers().clone();
-        Box::pin(body::to_bytes(req).map_err(ProstTwirpError::HyperError).map(move |result| result.map(move |body| {
-            ServiceRequest { uri, method, version, headers, input: body.to_vec() }
+        let body = read_body(&headers, req.into_body(), max_size);
+        Box::pin(body.map(move |result| result.map(move |input| {
+            ServiceRequest { uri, method, version, headers, peer_identity: None, input }
         })))
     }
 
     /// Turn a byte-array service request into a hyper request
-    pub fn to_hyper_raw(&self) -> Request {
-        let mut req = hyper::Request::post(&self.uri)
-            .body(self.input.clone().into())
-            .unwrap(); // TODO dont unwrap
+    pub fn to_hyper_raw(&self) -> Result<Request, ProstTwirpError> {
+        let mut req = hyper::Request::post(self.uri.clone()).
+            body(self.input.clone().into()).
+            map_err(ProstTwirpError::HttpError)?;
         req.headers_mut().clone_from(&self.headers);
         req.headers_mut().insert(header::CONTENT_LENGTH, self.input.len().into());
-        req
+        Ok(req)
     }
 
     /// Turn a byte-array service request into a `AfterBodyError`-wrapped version of the given error
@@ -111,6 +163,7 @@ impl ServiceRequest<Vec<u8>> {
     }
 }
 
+#[allow(clippy::result_large_err)]
 impl<T: Message + Default + 'static> ServiceRequest<T> {
     /// Turn a protobuf service request into a byte-array service request
     pub fn to_proto_raw(&self) -> Result<ServiceRequest<Vec<u8>>, ProstTwirpError> {
@@ -124,19 +177,12 @@ impl<T: Message + Default + 'static> ServiceRequest<T> {
 
     /// Turn a hyper request into a protobuf service request
     pub fn from_hyper_proto(req: Request) -> FutReq<T> {
-        // TODO: this used to be just Box::new(ServiceRequest::from_hyper_raw(req).and_then(|v| v.to_proto()))
-        let uri = req.uri().clone();
-        let method = req.method().clone();
-        let version = req.version();
-        let headers = req.headers().clone();
-        Box::pin(body::to_bytes(req).map_err(ProstTwirpError::HyperError).map(move |result| result.and_then(move |body| {
-            ServiceRequest { uri, method, version, headers, input: body.to_vec() }.to_proto()
-        })))
+        Box::pin(ServiceRequest::from_hyper_raw(req).map(|r| r.and_then(|v| v.to_proto())))
     }
 
     /// Turn a protobuf service request into a hyper request
     pub fn to_hyper_proto(&self) -> Result<Request, ProstTwirpError> {
-        self.to_proto_raw().map(|v| v.to_hyper_raw())
+        self.to_proto_raw().and_then(|v| v.to_hyper_raw())
     }
 }
 
@@ -164,7 +210,7 @@ impl<T> ServiceResponse<T> {
         headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/protobuf"));
         ServiceResponse {
             version: Version::default(),
-            headers: headers,
+            headers,
             status: StatusCode::OK,
             output
         }
@@ -180,24 +226,37 @@ impl<T: Message + Default + 'static> From<T> for ServiceResponse<T> {
     fn from(v: T) -> ServiceResponse<T> { ServiceResponse::new(v) }
 }
 
+#[allow(clippy::result_large_err)]
 impl ServiceResponse<Vec<u8>> {
     /// Turn a hyper response to a boxed future of a byte-array service response
     pub fn from_hyper_raw(resp: Response) -> PTRes<Vec<u8>> {
+        ServiceResponse::from_hyper_raw_max(resp, None)
+    }
+
+    /// Like `from_hyper_raw`, but failing with a `malformed` error instead of reading a body larger than `max_size`
+    pub fn from_hyper_raw_limited(resp: Response, max_size: usize) -> PTRes<Vec<u8>> {
+        ServiceResponse::from_hyper_raw_max(resp, Some(max_size))
+    }
+
+    fn from_hyper_raw_max(resp: Response, max_size: Option<usize>) -> PTRes<Vec<u8>> {
         let version = resp.version();
         let headers = resp.headers().clone();
         let status = resp.status();
-        Box::pin(body::to_bytes(resp).map_err(ProstTwirpError::HyperError).map(move |result| result.map(move |body| {
-            ServiceResponse { version, headers, status, output: body.to_vec() }
+        let body = read_body(&headers, resp.into_body(), max_size);
+        Box::pin(body.map(move |result| result.map(move |output| {
+            ServiceResponse { version, headers, status, output }
         })))
     }
 
     /// Turn a byte-array service response into a hyper response
-    pub fn to_hyper_raw(&self) -> Response {
-        let mut resp = Response::new(self.output.clone().into());
-        *resp.status_mut() = self.status;
+    pub fn to_hyper_raw(&self) -> Result<Response, ProstTwirpError> {
+        let mut resp = hyper::Response::builder().
+            status(self.status).
+            body(self.output.clone().into()).
+            map_err(ProstTwirpError::HttpError)?;
         resp.headers_mut().clone_from(&self.headers);
         resp.headers_mut().insert(header::CONTENT_LENGTH, self.output.len().into());
-        resp
+        Ok(resp)
     }
 
     /// Turn a byte-array service response into a `AfterBodyError`-wrapped version of the given error
@@ -209,21 +268,46 @@ impl ServiceResponse<Vec<u8>> {
     }
 
     /// Serialize the byte-array service response into a protobuf service response
+    ///
+    /// Successful responses must be protobuf, or have no `Content-Type` at all.
     pub fn to_proto<T: Message + Default + 'static>(&self) -> Result<ServiceResponse<T>, ProstTwirpError> {
         if self.status.is_success() {
-            match T::decode(&*self.output) {
-                Ok(v) => Ok(self.clone_with_output(v)),
-                Err(err) => Err(self.body_err(ProstTwirpError::ProstDecodeError(err)))
+            if let Some(content_type) = self.headers.get(header::CONTENT_TYPE).filter(|v| !is_protobuf(v)) {
+                let content_type = String::from_utf8_lossy(content_type.as_bytes()).into_owned();
+                return Err(self.body_err(ProstTwirpError::UnexpectedContentType(content_type)));
             }
+            self.decode_output()
         } else {
-            match TwirpError::from_json_bytes(self.status, &self.output) {
-                Ok(err) => Err(self.body_err(ProstTwirpError::TwirpError(err))),
-                Err(err) => Err(self.body_err(ProstTwirpError::JsonDecodeError(err)))
+            Err(self.body_err(ProstTwirpError::TwirpError(TwirpError::from_response(self))))
+        }
+    }
+
+    /// Serialize the byte-array Connect unary response into a protobuf service response
+    ///
+    /// Successful responses must be `application/proto`, or have no `Content-Type` at all. Connect errors are turned
+    /// into Twirp errors with `TwirpError::from_connect_response`.
+    pub fn to_connect_proto<T: Message + Default + 'static>(&self) -> Result<ServiceResponse<T>, ProstTwirpError> {
+        if self.status.is_success() {
+            let is_proto = |v: &&HeaderValue| media_type(v).as_deref() == Some(CONNECT_CONTENT_TYPE);
+            if let Some(content_type) = self.headers.get(header::CONTENT_TYPE).filter(|v| !is_proto(v)) {
+                let content_type = String::from_utf8_lossy(content_type.as_bytes()).into_owned();
+                return Err(self.body_err(ProstTwirpError::UnexpectedContentType(content_type)));
             }
+            self.decode_output()
+        } else {
+            Err(self.body_err(ProstTwirpError::TwirpError(TwirpError::from_connect_response(self))))
+        }
+    }
+
+    fn decode_output<T: Message + Default + 'static>(&self) -> Result<ServiceResponse<T>, ProstTwirpError> {
+        match T::decode(&*self.output) {
+            Ok(v) => Ok(self.clone_with_output(v)),
+            Err(err) => Err(self.body_err(ProstTwirpError::ProstDecodeError(err)))
         }
     }
 }
 
+#[allow(clippy::result_large_err)]
 impl<T: Message + Default + 'static> ServiceResponse<T> {
     /// Turn a protobuf service response into a byte-array service response
     pub fn to_proto_raw(&self) -> Result<ServiceResponse<Vec<u8>>, ProstTwirpError> {
@@ -242,7 +326,38 @@ impl<T: Message + Default + 'static> ServiceResponse<T> {
 
     /// Turn a protobuf service response into a hyper response
     pub fn to_hyper_proto(&self) -> Result<Response, ProstTwirpError> {
-        self.to_proto_raw().map(|v| v.to_hyper_raw())
+        self.to_proto_raw().and_then(|v| v.to_hyper_raw())
+    }
+}
+
+/// Read a request or response body, with an optional limit on its size
+///
+/// A body that is larger than the limit by its `Content-Length` or size hint isn't read at all, and one that turns out
+/// to be larger is only read up to the limit. Both fail with a `malformed` error.
+fn read_body(headers: &HeaderMap, mut body: Body, max_size: Option<usize>)
+        -> impl Future<Output=Result<Vec<u8>, ProstTwirpError>> + Send {
+    let content_length = headers.get(header::CONTENT_LENGTH).and_then(|v| v.to_str().ok()).
+        and_then(|v| v.parse::<u64>().ok());
+    async move {
+        let max_size = match max_size {
+            Some(max_size) => max_size,
+            None => return body::to_bytes(body).await.map(|v| v.to_vec()).map_err(ProstTwirpError::HyperError),
+        };
+        let too_large = || TwirpError::new(StatusCode::BAD_REQUEST, "malformed",
+            &format!("the body is larger than the maximum of {} bytes", max_size)).into();
+        let declared = content_length.unwrap_or(0).max(body.size_hint().lower());
+        if declared > max_size as u64 {
+            return Err(too_large());
+        }
+        let mut buf = Vec::with_capacity(declared as usize);
+        while let Some(chunk) = body.data().await {
+            let chunk = chunk.map_err(ProstTwirpError::HyperError)?;
+            if buf.len() + chunk.len() > max_size {
+                return Err(too_large());
+            }
+            buf.extend_from_slice(&chunk);
+        }
+        Ok(buf)
     }
 }
 
@@ -253,6 +368,8 @@ pub struct TwirpError {
     pub error_type: String,
     pub msg: String,
     pub meta: Option<serde_json::Value>,
+    /// The underlying error, which is never serialized and so stays on the server
+    pub cause: Option<Box<dyn StdError + Send + Sync>>,
 }
 
 impl TwirpError {
@@ -263,7 +380,47 @@ impl TwirpError {
 
     /// Create a Twirp error with optional meta
     pub fn new_meta(status: StatusCode, error_type: &str, msg: &str, meta: Option<serde_json::Value>) -> TwirpError {
-        TwirpError { status, error_type: error_type.to_string(), msg: msg.to_string(), meta }
+        TwirpError { status, error_type: error_type.to_string(), msg: msg.to_string(), meta, cause: None }
+    }
+
+    /// Create an `internal` Twirp error with a generic message for the given cause
+    ///
+    /// The cause is only visible to the client when the server is in development mode.
+    pub fn internal<E: Into<Box<dyn StdError + Send + Sync>>>(cause: E) -> TwirpError {
+        TwirpError::new(StatusCode::INTERNAL_SERVER_ERROR, "internal", "Internal Error").with_cause(cause)
+    }
+
+    /// Set the underlying error
+    pub fn with_cause<E: Into<Box<dyn StdError + Send + Sync>>>(mut self, cause: E) -> TwirpError {
+        self.cause = Some(cause.into());
+        self
+    }
+
+    /// The meta as the string map of the Twirp spec
+    ///
+    /// Values that are not strings are encoded as JSON text and null values are left out. Meta that is not an object
+    /// is put under a `value` key.
+    pub fn meta_map(&self) -> BTreeMap<String, String> {
+        match &self.meta {
+            None | Some(serde_json::Value::Null) => BTreeMap::new(),
+            Some(serde_json::Value::Object(meta)) => meta.iter().filter(|(_, value)| !value.is_null()).
+                map(|(key, value)| (key.clone(), meta_string(value))).collect(),
+            Some(meta) => vec![("value".to_string(), meta_string(meta))].into_iter().collect(),
+        }
+    }
+
+    /// The meta value for the key, if it is a string
+    pub fn meta_str(&self, key: &str) -> Option<&str> {
+        self.meta.as_ref().and_then(|meta| meta.get(key)).and_then(serde_json::Value::as_str)
+    }
+
+    /// Set the meta value for the key, keeping the other values of the `meta_map`
+    pub fn with_meta_str(mut self, key: &str, value: &str) -> TwirpError {
+        let mut meta: serde_json::Map<String, serde_json::Value> =
+            self.meta_map().into_iter().map(|(k, v)| (k, serde_json::Value::String(v))).collect();
+        meta.insert(key.to_string(), serde_json::Value::String(value.to_string()));
+        self.meta = Some(serde_json::Value::Object(meta));
+        self
     }
 
     /// Create a byte-array service response for this error and the given status code
@@ -274,32 +431,36 @@ impl TwirpError {
         headers.insert(header::CONTENT_LENGTH, output.len().into());
         ServiceResponse {
             version: Version::default(),
-            headers: headers,
+            headers,
             status: self.status,
             output
         }
     }
 
     /// Create a hyper response for this error and the given status code
-    pub fn to_hyper_resp(&self) -> Response {
-        let body = self.to_json_bytes().unwrap_or_else(|_| "{}".as_bytes().to_vec());
-        hyper::Response::builder().
-            status(self.status).
-            header(header::CONTENT_TYPE, HeaderValue::from_static("application/json")).
-            header(header::CONTENT_LENGTH, HeaderValue::from(body.len())).
-            body(body.into()).
-            unwrap() // TODO: don't unwrap?
+    #[allow(clippy::result_large_err)]
+    pub fn to_hyper_resp(&self) -> Result<Response, ProstTwirpError> {
+        self.to_resp_raw().to_hyper_raw()
     }
 
     /// Create error from Serde JSON value
+    ///
+    /// The error code is read from `code` as in the Twirp spec, falling back to the `error_type` written by older
+    /// versions of this crate. Like in the Twirp spec, a missing code or one that isn't in the spec becomes `internal`,
+    /// and an invalid code is kept as the `invalid_code` meta.
     pub fn from_json(status: StatusCode, json: serde_json::Value) -> TwirpError {
-        let error_type = json["error_type"].as_str();
-        TwirpError {
+        let error_type = json["code"].as_str().or_else(|| json["error_type"].as_str());
+        let err = TwirpError {
             status,
-            error_type: error_type.unwrap_or("<no code>").to_string(),
+            error_type: error_type.filter(|v| is_twirp_code(v)).unwrap_or("internal").to_string(),
             msg: json["msg"].as_str().unwrap_or("<no message>").to_string(),
             // Put the whole thing as meta if there was no type
-            meta: if error_type.is_some() { json.get("meta").map(|v| v.clone()) } else { Some(json.clone()) },
+            meta: if error_type.is_some() { json.get("meta").cloned() } else { Some(json.clone()) },
+            cause: None,
+        };
+        match error_type {
+            Some(code) if !is_twirp_code(code) => err.with_meta_str("invalid_code", code),
+            _ => err,
         }
     }
 
@@ -308,12 +469,99 @@ impl TwirpError {
         serde_json::from_slice(json).map(|v| TwirpError::from_json(status, v))
     }
 
+    /// Create error from a non-success response, synthesizing one like the Twirp spec when it isn't a Twirp error
+    ///
+    /// Responses without a Twirp error JSON body, e.g. from a proxy or load balancer, get a code derived from the HTTP
+    /// status, and `http_error_from_intermediary`, `status_code` and either `location` for redirects or a `body`
+    /// snippet as meta. JSON objects without a code become `internal` errors with the body snippet as meta.
+    pub fn from_response(resp: &ServiceResponse<Vec<u8>>) -> TwirpError {
+        let status = resp.status;
+        if status.is_redirection() {
+            let location = resp.headers.get(header::LOCATION).and_then(|v| v.to_str().ok()).unwrap_or("");
+            let msg = format!("unexpected HTTP status code {} {:?} received, Location={:?}",
+                status.as_u16(), status.canonical_reason().unwrap_or(""), location);
+            return TwirpError::from_intermediary(status, &msg).with_meta_str("location", location);
+        }
+        match serde_json::from_slice::<serde_json::Value>(&resp.output) {
+            Ok(json @ serde_json::Value::Object(_)) =>
+                if json["code"].is_string() || json["error_type"].is_string() {
+                    TwirpError::from_json(status, json)
+                } else {
+                    TwirpError::new(status, "internal", "invalid type returned from server error response: ").
+                        with_meta_str("body", &body_snippet(&resp.output))
+                },
+            json => {
+                let msg = format!("Error from intermediary with HTTP status code {} {:?}",
+                    status.as_u16(), status.canonical_reason().unwrap_or(""));
+                let err = TwirpError::from_intermediary(status, &msg).
+                    with_meta_str("body", &body_snippet(&resp.output));
+                match json {
+                    Err(cause) => err.with_cause(cause),
+                    Ok(_) => err,
+                }
+            },
+        }
+    }
+
+    /// Create error from a non-success Connect unary response
+    ///
+    /// The Connect code is mapped to the matching Twirp code, e.g. `data_loss` to `dataloss`, and the status is kept.
+    /// Responses without a Connect error JSON body are handled like in `from_response`.
+    pub fn from_connect_response(resp: &ServiceResponse<Vec<u8>>) -> TwirpError {
+        let json: serde_json::Value = match serde_json::from_slice(&resp.output) {
+            Ok(json) => json,
+            Err(_) => return TwirpError::from_response(resp),
+        };
+        match json["code"].as_str() {
+            Some(code) => TwirpError::new(resp.status, twirp_code(code), json["message"].as_str().unwrap_or("")),
+            None => TwirpError::from_response(resp),
+        }
+    }
+
+    /// Create a byte-array Connect unary error response for this error
+    ///
+    /// The Twirp code is mapped to the matching Connect code, e.g. `bad_route` to `unimplemented` and `malformed` to
+    /// `invalid_argument`, and the status is the one Connect uses for that code. The meta is not sent, Connect unary
+    /// errors only have a code and a message.
+    pub fn to_connect_resp_raw(&self) -> ServiceResponse<Vec<u8>> {
+        let code = connect_code(&self.error_type);
+        let mut json = serde_json::json!({ "code": code });
+        if !self.msg.is_empty() {
+            json["message"] = serde_json::Value::String(self.msg.clone());
+        }
+        let output = json.to_string().into_bytes();
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        headers.insert(header::CONTENT_LENGTH, output.len().into());
+        ServiceResponse { version: Version::default(), headers, status: connect_status(code), output }
+    }
+
+    /// An error for a non-Twirp response with the given status, with the code mapped like the Twirp spec
+    fn from_intermediary(status: StatusCode, msg: &str) -> TwirpError {
+        let code = match status.as_u16() {
+            300..=400 => "internal",
+            401 => "unauthenticated",
+            403 => "permission_denied",
+            404 => "bad_route",
+            429 | 502 | 503 | 504 => "unavailable",
+            _ => "unknown",
+        };
+        TwirpError::new(status, code, msg).
+            with_meta_str("http_error_from_intermediary", "true").
+            with_meta_str("status_code", &status.as_u16().to_string())
+    }
+
     /// Create Serde JSON value from error
     pub fn to_json(&self) -> serde_json::Value {
         let mut props = serde_json::map::Map::new();
-        props.insert("error_type".to_string(), serde_json::Value::String(self.error_type.clone()));
+        props.insert("code".to_string(), serde_json::Value::String(self.error_type.clone()));
         props.insert("msg".to_string(), serde_json::Value::String(self.msg.clone()));
-        if let Some(ref meta) = self.meta { props.insert("meta".to_string(), meta.clone()); }
+        // Meta is always written as a string map as required by the spec
+        let meta = self.meta_map();
+        if !meta.is_empty() {
+            props.insert("meta".to_string(),
+                serde_json::Value::Object(meta.into_iter().map(|(k, v)| (k, serde_json::Value::String(v))).collect()));
+        }
         serde_json::Value::Object(props)
     }
 
@@ -323,11 +571,94 @@ impl TwirpError {
     }
 }
 
+/// Whether the code is one of the error codes of the Twirp spec
+fn is_twirp_code(code: &str) -> bool {
+    matches!(code, "canceled" | "unknown" | "invalid_argument" | "malformed" | "deadline_exceeded" | "not_found" |
+        "bad_route" | "already_exists" | "permission_denied" | "unauthenticated" | "resource_exhausted" |
+        "failed_precondition" | "aborted" | "out_of_range" | "unimplemented" | "internal" | "unavailable" | "dataloss")
+}
+
+/// The Connect error code for a Twirp error code, `unknown` for codes that aren't in the Twirp spec
+fn connect_code(twirp_code: &str) -> &'static str {
+    match twirp_code {
+        "canceled" => "canceled",
+        "invalid_argument" | "malformed" => "invalid_argument",
+        "deadline_exceeded" => "deadline_exceeded",
+        "not_found" => "not_found",
+        "already_exists" => "already_exists",
+        "permission_denied" => "permission_denied",
+        "unauthenticated" => "unauthenticated",
+        "resource_exhausted" => "resource_exhausted",
+        "failed_precondition" => "failed_precondition",
+        "aborted" => "aborted",
+        "out_of_range" => "out_of_range",
+        "unimplemented" | "bad_route" => "unimplemented",
+        "internal" => "internal",
+        "unavailable" => "unavailable",
+        "dataloss" => "data_loss",
+        _ => "unknown",
+    }
+}
+
+/// The Twirp error code for a Connect error code, which are the same but for `data_loss`
+fn twirp_code(connect_code: &str) -> &str {
+    if connect_code == "data_loss" { "dataloss" } else { connect_code }
+}
+
+/// The HTTP status of a Connect unary error response for the Connect error code
+fn connect_status(connect_code: &str) -> StatusCode {
+    match connect_code {
+        "canceled" => StatusCode::from_u16(499).expect("499 is a valid status code"),
+        "invalid_argument" | "failed_precondition" | "out_of_range" => StatusCode::BAD_REQUEST,
+        "deadline_exceeded" => StatusCode::GATEWAY_TIMEOUT,
+        "not_found" => StatusCode::NOT_FOUND,
+        "already_exists" | "aborted" => StatusCode::CONFLICT,
+        "permission_denied" => StatusCode::FORBIDDEN,
+        "resource_exhausted" => StatusCode::TOO_MANY_REQUESTS,
+        "unimplemented" => StatusCode::NOT_IMPLEMENTED,
+        "unavailable" => StatusCode::SERVICE_UNAVAILABLE,
+        "unauthenticated" => StatusCode::UNAUTHORIZED,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// The start of a response body as text, for error meta
+fn body_snippet(body: &[u8]) -> String {
+    const MAX_LEN: usize = 1024;
+    let mut snippet = String::from_utf8_lossy(&body[..body.len().min(MAX_LEN)]).into_owned();
+    if body.len() > MAX_LEN {
+        // A cut multi-byte character has become a replacement character, which is fine for a snippet
+        snippet.push_str("...");
+    }
+    snippet
+}
+
+/// A meta value as a string, with anything but strings encoded as JSON text
+fn meta_string(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+impl fmt::Display for TwirpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { write!(f, "{}: {}", self.error_type, self.msg) }
+}
+
+impl StdError for TwirpError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.cause.as_ref().map(|v| &**v as &(dyn StdError + 'static))
+    }
+}
+
 impl From<TwirpError> for ProstTwirpError {
     fn from(v: TwirpError) -> ProstTwirpError { ProstTwirpError::TwirpError(v) }
 }
 
 /// An error that can occur during a call to a Twirp service
+///
+/// It is large, as `AfterBodyError` keeps the details of the request or response, so the functions returning it allow
+/// `clippy::result_large_err`.
 #[derive(Debug)]
 pub enum ProstTwirpError {
     /// A standard Twirp error with a type, message, and some metadata
@@ -340,6 +671,10 @@ pub enum ProstTwirpError {
     ProstDecodeError(DecodeError),
     /// A generic hyper error
     HyperError(hyper::Error),
+    /// An error when trying to build a hyper request or response
+    HttpError(http::Error),
+    /// A response or request with a `Content-Type` other than protobuf, where protobuf was expected
+    UnexpectedContentType(String),
     /// An error when trying to construct an URI and this shouldn't really happen.
     // TODO
     UriError(InvalidUri),
@@ -368,51 +703,511 @@ impl ProstTwirpError {
             _ => self
         }
     }
+
+    /// The Twirp error, if this is one, possibly wrapped in an `AfterBodyError`
+    pub fn twirp_error(&self) -> Option<&TwirpError> {
+        match self {
+            ProstTwirpError::TwirpError(err) => Some(err),
+            ProstTwirpError::AfterBodyError { err, .. } => err.twirp_error(),
+            _ => None
+        }
+    }
+
+    /// The names of the variants from this error down to the `root_err`, e.g. `AfterBodyError > ProstDecodeError`
+    pub fn root_err_path(&self) -> String {
+        match self {
+            ProstTwirpError::TwirpError(_) => "TwirpError".to_string(),
+            ProstTwirpError::JsonDecodeError(_) => "JsonDecodeError".to_string(),
+            ProstTwirpError::ProstEncodeError(_) => "ProstEncodeError".to_string(),
+            ProstTwirpError::ProstDecodeError(_) => "ProstDecodeError".to_string(),
+            ProstTwirpError::HyperError(_) => "HyperError".to_string(),
+            ProstTwirpError::HttpError(_) => "HttpError".to_string(),
+            ProstTwirpError::UnexpectedContentType(_) => "UnexpectedContentType".to_string(),
+            ProstTwirpError::UriError(_) => "UriError".to_string(),
+            ProstTwirpError::AfterBodyError { err, .. } => format!("AfterBodyError > {}", err.root_err_path()),
+        }
+    }
+
+    /// The `root_err_path` followed by the messages of this error and all of its sources, e.g. for logging
+    pub fn cause_chain(&self) -> String {
+        let mut chain = format!("{}: {}", self.root_err_path(), self);
+        let mut source = self.source();
+        while let Some(err) = source {
+            chain.push_str(": ");
+            chain.push_str(&err.to_string());
+            source = err.source();
+        }
+        chain
+    }
+}
+
+impl fmt::Display for ProstTwirpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProstTwirpError::TwirpError(err) => err.fmt(f),
+            ProstTwirpError::JsonDecodeError(_) => f.write_str("failed to decode JSON"),
+            ProstTwirpError::ProstEncodeError(_) => f.write_str("failed to encode protobuf"),
+            ProstTwirpError::ProstDecodeError(_) => f.write_str("failed to decode protobuf"),
+            ProstTwirpError::HyperError(_) => f.write_str("HTTP error"),
+            ProstTwirpError::HttpError(_) => f.write_str("failed to build HTTP message"),
+            ProstTwirpError::UnexpectedContentType(content_type) =>
+                write!(f, "unexpected Content-Type: {:?}", content_type),
+            ProstTwirpError::UriError(_) => f.write_str("invalid URI"),
+            ProstTwirpError::AfterBodyError { err, .. } => err.fmt(f),
+        }
+    }
+}
+
+impl StdError for ProstTwirpError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            ProstTwirpError::TwirpError(err) => err.source(),
+            ProstTwirpError::JsonDecodeError(err) => Some(err),
+            ProstTwirpError::ProstEncodeError(err) => Some(err),
+            ProstTwirpError::ProstDecodeError(err) => Some(err),
+            ProstTwirpError::HyperError(err) => Some(err),
+            ProstTwirpError::HttpError(err) => Some(err),
+            ProstTwirpError::UnexpectedContentType(_) => None,
+            ProstTwirpError::UriError(err) => Some(err),
+            ProstTwirpError::AfterBodyError { err, .. } => err.source(),
+        }
+    }
+}
+
+/// Which side of an RPC is being observed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RpcKind {
+    /// A call made through `HyperClient`
+    Client,
+    /// A request handled by `HyperServer`
+    Server,
+}
+
+/// The RPC passed to `RpcHooks`
+#[derive(Debug, Clone)]
+pub struct RpcInfo {
+    /// An id telling RPCs apart, unique within the process
+    pub id: u64,
+    /// Whether this is a client call or a served request
+    pub kind: RpcKind,
+    /// The request path
+    pub path: String,
+    /// The fully-qualified service name, empty if the path is not a `/twirp/<service>/<method>` route
+    pub service: String,
+    /// The method name, empty if the path is not a `/twirp/<service>/<method>` route
+    pub method: String,
+}
+
+impl RpcInfo {
+    /// Create the info for an RPC on the given request path
+    pub fn new(kind: RpcKind, path: &str) -> RpcInfo {
+        let mut parts = path.trim_start_matches('/').splitn(3, '/');
+        let (service, method) = match (parts.next(), parts.next(), parts.next()) {
+            (Some("twirp"), Some(service), Some(method)) => (service, method),
+            _ => ("", ""),
+        };
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        RpcInfo {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            kind,
+            path: path.to_string(),
+            service: service.to_string(),
+            method: method.to_string(),
+        }
+    }
+}
+
+/// How an RPC ended, as passed to `RpcHooks`
+#[derive(Debug, Clone)]
+pub struct RpcOutcome {
+    /// The HTTP status of the response, if there was one
+    pub status: Option<StatusCode>,
+    /// The Twirp error code if the RPC failed
+    ///
+    /// Failures that are not Twirp errors, like connection or decoding errors, are reported as `internal`.
+    pub error_code: Option<String>,
+    /// The size of the request body
+    pub request_size: usize,
+    /// The size of the response body
+    pub response_size: usize,
+    /// The time from the start of the RPC until the response was ready
+    pub latency: Duration,
+}
+
+impl RpcOutcome {
+    /// The Twirp error code, or `ok` if the RPC succeeded
+    pub fn code(&self) -> &str { self.error_code.as_deref().unwrap_or("ok") }
+}
+
+/// Callbacks to observe the RPCs of a `HyperClient` or `HyperServer`, e.g. to collect metrics
+///
+/// Every RPC that starts is also completed exactly once, except for server requests whose connection is dropped before
+/// the response is ready. Server requests are always finished though, see `request_finished`.
+pub trait RpcHooks: Send + Sync {
+    /// Called when a request is about to be sent or was received
+    fn request_started(&self, _rpc: &RpcInfo) {}
+
+    /// Called when a response was received or is about to be sent, or the RPC failed without one
+    fn request_completed(&self, _rpc: &RpcInfo, _outcome: &RpcOutcome) {}
+
+    /// Called exactly once for every server request that started, last, when `HyperServer` is done with it
+    ///
+    /// Unlike `request_completed`, this is also called when the request is dropped, e.g. because the client
+    /// disconnected, so it's where to let go of anything kept per request.
+    fn request_finished(&self, _rpc: &RpcInfo) {}
+
+    /// Called for every limit of the `ConcurrencyLimit` of the `HyperServer` a server request found full, once it was
+    /// served or shed
+    ///
+    /// Shed requests are also completed, with the error they are answered with.
+    fn request_limited(&self, _rpc: &RpcInfo, _outcome: &LimitOutcome) {}
+}
+
+/// How a server request fared against a full `ConcurrencyLimit`, as passed to `RpcHooks::request_limited`
+#[derive(Debug, Clone)]
+pub struct LimitOutcome {
+    /// The name of the limit that was full, e.g. `global` or the path of a method
+    pub limit: String,
+    /// How long the request waited for room
+    pub waited: Duration,
+    /// Whether the request was shed instead of served
+    pub shed: bool,
+}
+
+/// A limit on the requests a `HyperServer` serves at once, checked before the request body is read
+pub trait ConcurrencyLimit: Send + Sync {
+    /// Wait for room to serve the RPC, or give up on it
+    fn acquire(&self, rpc: &RpcInfo) -> Pin<Box<dyn Future<Output=Admission> + Send>>;
+}
+
+/// Whether a server request may be served under a `ConcurrencyLimit`
+pub struct Admission {
+    /// A guard holding the room of the request until it's dropped, or the error to shed the request with
+    pub permit: Result<Box<dyn Any + Send>, TwirpError>,
+    /// How the request fared against every limit that was full, in the order they were checked, empty if there was
+    /// room right away
+    pub limited: Vec<LimitOutcome>,
+}
+
+/// One access log record per request handled by `HyperServer`
+#[derive(Debug, Clone)]
+pub struct AccessLogRecord {
+    /// When the request was received
+    pub timestamp: SystemTime,
+    /// The remote address of the connection, if set with `HyperServer::with_peer`
+    pub peer: Option<SocketAddr>,
+    /// The HTTP method of the request
+    pub http_method: Method,
+    /// The route of the request
+    pub rpc: RpcInfo,
+    /// The content type of the request
+    pub content_type: Option<String>,
+    /// The request headers on the `AccessLog` allow-list, in request order
+    pub headers: Vec<(String, String)>,
+    /// How the request ended
+    pub outcome: RpcOutcome,
+}
+
+/// Where access log records are written to
+pub trait AccessLogSink: Send + Sync {
+    /// Write the record
+    fn log(&self, record: &AccessLogRecord);
+}
+
+/// Access logging for `HyperServer`, writing one record per request to a sink
+///
+/// Request headers are only logged if they are on the allow-list, which is empty by default, so credentials like
+/// `Authorization` never end up in the log unless explicitly asked for.
+#[derive(Clone)]
+pub struct AccessLog {
+    sink: Arc<dyn AccessLogSink>,
+    headers: Vec<HeaderName>,
+}
+
+impl std::fmt::Debug for AccessLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AccessLog").field("headers", &self.headers).finish()
+    }
+}
+
+impl AccessLog {
+    /// Create a new access log writing to the given sink
+    pub fn new<S: AccessLogSink + 'static>(sink: S) -> AccessLog {
+        AccessLog { sink: Arc::new(sink), headers: Vec::new() }
+    }
+
+    /// Add a request header to the allow-list of logged headers
+    pub fn with_header(mut self, name: HeaderName) -> AccessLog {
+        self.headers.push(name);
+        self
+    }
+
+    fn allowed_headers(&self, headers: &HeaderMap) -> Vec<(String, String)> {
+        headers.iter().filter(|(name, _)| self.headers.contains(name)).
+            map(|(name, value)| (name.to_string(), String::from_utf8_lossy(value.as_bytes()).into_owned())).
+            collect()
+    }
 }
 
 /// A wrapper for a hyper client
-#[derive(Debug)]
-pub struct HyperClient {
+///
+/// The connector defaults to plain HTTP, other ones like an HTTPS connector work the same.
+pub struct HyperClient<C = HttpConnector> {
     /// The hyper client
-    pub client: Client<HttpConnector, Body>,
+    pub client: Client<C, Body>,
     /// The root URL without any path attached
     pub root_url: String,
+    /// The hooks called for every call
+    pub hooks: Vec<Arc<dyn RpcHooks>>,
+    /// The protocol calls are made with
+    pub protocol: Protocol,
+    /// The timeout sent to Connect servers, which Twirp has no header for
+    pub timeout: Option<Duration>,
+    /// The largest response body read, larger ones fail the call with a `malformed` error
+    pub max_body_size: Option<usize>,
+}
+
+/// The wire protocol of a `HyperClient`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Protocol {
+    /// Twirp with protobuf bodies, the default
+    #[default]
+    Twirp,
+    /// The unary subset of the Connect protocol with protobuf bodies, as served by `ConnectServer`
+    ///
+    /// Calls go to `/package.Service/Method` instead of `/twirp/package.Service/Method` and errors are turned into
+    /// Twirp errors with `TwirpError::from_connect_response`.
+    Connect,
 }
 
-impl HyperClient {
+impl<C> std::fmt::Debug for HyperClient<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HyperClient").field("client", &self.client).field("root_url", &self.root_url).
+            field("hooks", &self.hooks.len()).field("protocol", &self.protocol).field("timeout", &self.timeout).
+            field("max_body_size", &self.max_body_size).finish()
+    }
+}
+
+impl<C: Connect + Clone + Send + Sync + 'static> HyperClient<C> {
     /// Create a new client wrapper for the given client and root using protobuf
-    pub fn new(client: Client<HttpConnector, Body>, root_url: &str) -> HyperClient {
+    pub fn new(client: Client<C, Body>, root_url: &str) -> HyperClient<C> {
         HyperClient {
             client,
             root_url: root_url.trim_end_matches('/').to_string(),
+            hooks: Vec::new(),
+            protocol: Protocol::Twirp,
+            timeout: None,
+            max_body_size: None,
         }
     }
 
+    /// Add hooks to be called for every call
+    pub fn with_hooks(mut self, hooks: Arc<dyn RpcHooks>) -> HyperClient<C> {
+        self.hooks.push(hooks);
+        self
+    }
+
+    /// Set the protocol calls are made with
+    pub fn with_protocol(mut self, protocol: Protocol) -> HyperClient<C> {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Set the timeout sent to Connect servers in `Connect-Timeout-Ms`
+    ///
+    /// The server answers with `deadline_exceeded` when it doesn't make it in time. Twirp calls have no timeout header
+    /// and ignore this.
+    pub fn with_timeout(mut self, timeout: Duration) -> HyperClient<C> {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Fail calls with a `malformed` error instead of reading a response body larger than `max_size` bytes
+    ///
+    /// Response bodies are read whole by default, whatever their size.
+    pub fn with_max_body_size(mut self, max_size: usize) -> HyperClient<C> {
+        self.max_body_size = Some(max_size);
+        self
+    }
+
     /// Invoke the given request for the given path and return a boxed future result
+    #[allow(clippy::result_large_err)]
     pub fn go<I, O>(&self, path: &str, req: ServiceRequest<I>) -> PTRes<O>
             where I: Message + Default + 'static, O: Message + Default + 'static {
-        // Build the URI
-        let uri = match format!("{}/{}", self.root_url, path.trim_start_matches('/')).parse() {
+        // Build the URI, Connect routes are the Twirp ones without the prefix
+        let protocol = self.protocol;
+        let route = match protocol {
+            Protocol::Twirp => path,
+            Protocol::Connect => path.strip_prefix("/twirp").unwrap_or(path),
+        };
+        let uri: Uri = match format!("{}/{}", self.root_url, route.trim_start_matches('/')).parse() {
             Err(err) => return Box::pin(future::ready(Err(ProstTwirpError::UriError(err)))),
             Ok(v) => v,
         };
+        let start = Instant::now();
+        let rpc = match protocol {
+            Protocol::Twirp => RpcInfo::new(RpcKind::Client, uri.path()),
+            Protocol::Connect => RpcInfo { path: uri.path().to_string(), ..RpcInfo::new(RpcKind::Client, path) },
+        };
+        let timeout = self.timeout;
+        let max_body_size = self.max_body_size;
+        let hooks = self.hooks.clone();
+        hooks.iter().for_each(|h| h.request_started(&rpc));
+        let span = rpc_trace::RpcSpan::client(&rpc);
         // Build the request
-        let mut hyper_req = match req.to_hyper_proto() {
-            Err(err) => return Box::pin(future::ready(Err(err))),
-            Ok(v) => v
+        let built = req.to_proto_raw().and_then(|mut v| {
+            if protocol == Protocol::Connect {
+                v.headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(CONNECT_CONTENT_TYPE));
+                v.headers.insert(HeaderName::from_static("connect-protocol-version"), HeaderValue::from_static("1"));
+                if let Some(timeout) = timeout {
+                    // The header has at most 10 digits
+                    let millis = timeout.as_millis().min(9_999_999_999) as u64;
+                    v.headers.insert(HeaderName::from_static("connect-timeout-ms"), millis.into());
+                }
+            }
+            span.inject_context(&mut v.headers);
+            v.to_hyper_raw().map(|hyper_req| (hyper_req, v.input.len()))
+        });
+        let (mut hyper_req, request_size) = match built {
+            Err(err) => {
+                let outcome = RpcOutcome::failed(&err, None, 0, 0, start);
+                span.in_scope(|| rpc_trace::record_outcome(&outcome));
+                hooks.iter().for_each(|h| h.request_completed(&rpc, &outcome));
+                return Box::pin(future::ready(Err(err)));
+            },
+            Ok(v) => v,
         };
         *hyper_req.uri_mut() = uri;
         // Run the request and map the response
-        Box::pin(self.client.request(hyper_req).
-            map_err(ProstTwirpError::HyperError).
-            and_then(ServiceResponse::from_hyper_proto))
+        let resp = self.client.request(hyper_req);
+        span.instrument(Box::pin(async move {
+            let raw = match resp.await {
+                Ok(resp) => ServiceResponse::from_hyper_raw_max(resp, max_body_size).await,
+                Err(err) => Err(ProstTwirpError::HyperError(err)),
+            };
+            let (status, response_size) = raw.as_ref().map_or((None, 0), |v| (Some(v.status), v.output.len()));
+            let result = raw.and_then(|v| match protocol {
+                Protocol::Twirp => v.to_proto(),
+                Protocol::Connect => v.to_connect_proto(),
+            });
+            let outcome = match &result {
+                Ok(_) => RpcOutcome::succeeded(status, request_size, response_size, start),
+                Err(err) => RpcOutcome::failed(err, status, request_size, response_size, start),
+            };
+            rpc_trace::record_outcome(&outcome);
+            hooks.iter().for_each(|h| h.request_completed(&rpc, &outcome));
+            result
+        }))
+    }
+}
+
+impl RpcOutcome {
+    fn succeeded(status: Option<StatusCode>, request_size: usize, response_size: usize, start: Instant) -> RpcOutcome {
+        RpcOutcome { status, error_code: None, request_size, response_size, latency: start.elapsed() }
+    }
+
+    fn failed(err: &ProstTwirpError, status: Option<StatusCode>, request_size: usize, response_size: usize,
+            start: Instant) -> RpcOutcome {
+        let error_code = err.twirp_error().map_or("internal", |v| &v.error_type).to_string();
+        RpcOutcome { status, error_code: Some(error_code), request_size, response_size, latency: start.elapsed() }
+    }
+}
+
+/// A static description of a Twirp service, as emitted by the code generator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServiceDescriptor {
+    /// The protobuf package, empty if the proto file has none
+    pub package: &'static str,
+    /// The service name as it appears in the proto file
+    pub name: &'static str,
+    /// The fully-qualified service name, e.g. `twitch.twirp.example.Haberdasher`
+    pub full_name: &'static str,
+    /// The methods of the service in proto file order
+    pub methods: &'static [MethodDescriptor],
+}
+
+impl ServiceDescriptor {
+    /// The method for the given proto name, if any
+    pub fn method(&self, proto_name: &str) -> Option<&'static MethodDescriptor> {
+        self.methods.iter().find(|m| m.proto_name == proto_name)
+    }
+
+    /// The method routed at the given request path, if any
+    pub fn method_for_path(&self, path: &str) -> Option<&'static MethodDescriptor> {
+        self.methods.iter().find(|m| m.path == path)
     }
 }
 
+/// A static description of a single method of a Twirp service
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MethodDescriptor {
+    /// The name of the generated Rust method, without any `r#` prefix
+    pub name: &'static str,
+    /// The method name as it appears in the proto file
+    pub proto_name: &'static str,
+    /// The request path, e.g. `/twirp/twitch.twirp.example.Haberdasher/MakeHat`
+    pub path: &'static str,
+    /// The fully-qualified protobuf type of the input, e.g. `twitch.twirp.example.Size`
+    pub input_type: &'static str,
+    /// The fully-qualified protobuf type of the output, e.g. `twitch.twirp.example.Hat`
+    pub output_type: &'static str,
+}
+
 /// Service for taking a raw service request and returning a boxed future of a raw service response
 pub trait HyperService {
     /// Accept a raw service request and return a boxed future of a raw service response
     fn handle(&self, req: ServiceRequest<Vec<u8>>) -> PTRes<Vec<u8>>;
+
+    /// The descriptor of the service being handled, if known
+    ///
+    /// Generated servers always return their service's descriptor. Hand-written services may leave this as `None`.
+    fn descriptor(&self) -> Option<&'static ServiceDescriptor> { None }
+}
+
+/// The request an error happened for, as passed to an `ErrorHandler`
+#[derive(Debug, Clone)]
+pub struct ErrorContext {
+    /// The route of the request
+    pub rpc: RpcInfo,
+    /// The HTTP method of the request
+    pub http_method: Method,
+    /// The request headers
+    pub headers: HeaderMap,
+}
+
+/// Turns the errors of a `HyperServer` into the Twirp errors it responds with
+///
+/// This gets every error: invalid routes, bodies that fail to decode, errors returned by the service and panics, which
+/// are passed as `internal` Twirp errors. Returning a hyper error drops the connection instead of responding.
+pub trait ErrorHandler: Send + Sync {
+    /// Map the error of the given request
+    fn handle_error(&self, ctx: &ErrorContext, err: ProstTwirpError) -> Result<TwirpError, hyper::Error>;
+}
+
+impl<F> ErrorHandler for F
+        where F: Fn(&ErrorContext, ProstTwirpError) -> Result<TwirpError, hyper::Error> + Send + Sync {
+    fn handle_error(&self, ctx: &ErrorContext, err: ProstTwirpError) -> Result<TwirpError, hyper::Error> {
+        self(ctx, err)
+    }
+}
+
+/// The error handler used by default, mapping errors the way the Twirp spec and the Go implementation do
+///
+/// Twirp errors are responded with as is, undecodable request bodies become `malformed` errors and hyper errors are
+/// propagated. Everything else becomes an `internal` error without any details.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultErrorHandler;
+
+impl ErrorHandler for DefaultErrorHandler {
+    fn handle_error(&self, _ctx: &ErrorContext, err: ProstTwirpError) -> Result<TwirpError, hyper::Error> {
+        match err.root_err() {
+            ProstTwirpError::TwirpError(err) => Ok(err),
+            ProstTwirpError::ProstDecodeError(_) | ProstTwirpError::JsonDecodeError(_) =>
+                Ok(TwirpError::new(StatusCode::BAD_REQUEST, "malformed", "the request could not be decoded")),
+            ProstTwirpError::HyperError(err) => Err(err),
+            _ => Ok(TwirpError::new(StatusCode::INTERNAL_SERVER_ERROR, "internal", "Internal Error")),
+        }
+    }
 }
 
 /// A wrapper for a `HyperService` trait that keeps a `Arc` version of the service
@@ -420,12 +1215,150 @@ pub struct HyperServer<T: 'static + HyperService> {
     /// The `Arc` version of the service
     /// 
     /// Needed because of [hyper Service lifetimes](https://github.com/tokio-rs/tokio-service/issues/9)
-    pub service: Arc<T>
+    pub service: Arc<T>,
+    /// The hooks called for every request
+    pub hooks: Vec<Arc<dyn RpcHooks>>,
+    /// The access log every request is written to
+    pub access_log: Option<AccessLog>,
+    /// The remote address of the connection this server is serving, for the access log
+    pub peer: Option<SocketAddr>,
+    /// The identity the client of the connection this server is serving proved with its TLS certificate
+    pub peer_identity: Option<Arc<PeerIdentity>>,
+    /// The hook called when the service panics
+    pub panic_hook: Option<PanicHook>,
+    /// The handler turning errors into Twirp errors to respond with
+    pub error_handler: Arc<dyn ErrorHandler>,
+    /// Whether to add the `cause_chain` of errors to the `cause` meta of the responses, only meant for development
+    pub dev_mode: bool,
+    /// The limit on the requests served at once, shared by the clones of this server
+    pub concurrency_limit: Option<Arc<dyn ConcurrencyLimit>>,
+    /// The largest request body read, larger ones are answered with a `malformed` error
+    pub max_body_size: Option<usize>,
+}
+
+/// A hook receiving the route and the payload of a panic in a `HyperService`, e.g. to report it
+///
+/// See `panic_message` to get the message out of the payload.
+pub type PanicHook = Arc<dyn Fn(&RpcInfo, &(dyn Any + Send)) + Send + Sync>;
+
+/// The message of a panic payload, if it was a string as is the case for `panic!` and friends
+pub fn panic_message(payload: &(dyn Any + Send)) -> Option<&str> {
+    payload.downcast_ref::<&'static str>().copied().or_else(|| payload.downcast_ref::<String>().map(String::as_str))
 }
 
 impl<T: 'static + HyperService> HyperServer<T> {
     /// Create a new service wrapper for the given impl
-    pub fn new(service: T) -> HyperServer<T> { HyperServer { service: Arc::new(service) } }
+    pub fn new(service: T) -> HyperServer<T> {
+        HyperServer {
+            service: Arc::new(service),
+            hooks: Vec::new(),
+            access_log: None,
+            peer: None,
+            peer_identity: None,
+            panic_hook: None,
+            error_handler: Arc::new(DefaultErrorHandler),
+            dev_mode: false,
+            concurrency_limit: None,
+            max_body_size: None,
+        }
+    }
+
+    /// Add hooks to be called for every request
+    pub fn with_hooks(mut self, hooks: Arc<dyn RpcHooks>) -> HyperServer<T> {
+        self.hooks.push(hooks);
+        self
+    }
+
+    /// Write a record of every request to the given access log
+    pub fn with_access_log(mut self, access_log: AccessLog) -> HyperServer<T> {
+        self.access_log = Some(access_log);
+        self
+    }
+
+    /// Set the remote address of the connection, usually from hyper's `AddrStream::remote_addr` in `make_service_fn`
+    pub fn with_peer(mut self, peer: SocketAddr) -> HyperServer<T> {
+        self.peer = Some(peer);
+        self
+    }
+
+    /// Set the identity the client of the connection proved with its TLS certificate, which services see in
+    /// `ServiceRequest::peer_identity`
+    pub fn with_peer_identity(mut self, peer_identity: PeerIdentity) -> HyperServer<T> {
+        self.peer_identity = Some(Arc::new(peer_identity));
+        self
+    }
+
+    /// Call the given hook when the service panics
+    ///
+    /// Panics in the service are always caught and answered with an `internal` error, the hook is only for reporting.
+    pub fn with_panic_hook<F: Fn(&RpcInfo, &(dyn Any + Send)) + Send + Sync + 'static>(mut self, hook: F)
+            -> HyperServer<T> {
+        self.panic_hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Use the given handler instead of the `DefaultErrorHandler` to turn errors into Twirp errors
+    pub fn with_error_handler<H: ErrorHandler + 'static>(mut self, error_handler: H) -> HyperServer<T> {
+        self.error_handler = Arc::new(error_handler);
+        self
+    }
+
+    /// Set whether to tell clients the `cause_chain` of errors in the `cause` meta of the error response
+    ///
+    /// This exposes server internals and is only meant for development. Twirp errors without a cause are left as is.
+    pub fn with_dev_mode(mut self, dev_mode: bool) -> HyperServer<T> {
+        self.dev_mode = dev_mode;
+        self
+    }
+
+    /// Wait for room under the given limit before serving each request, or shed it, see `ConcurrencyLimit`
+    ///
+    /// The limit is shared by the clones of this server, so it applies across the connections they serve.
+    pub fn with_concurrency_limit<L: ConcurrencyLimit + 'static>(mut self, limit: L) -> HyperServer<T> {
+        self.concurrency_limit = Some(Arc::new(limit));
+        self
+    }
+
+    /// Answer requests with a body larger than `max_size` bytes with a `malformed` error instead of reading them
+    ///
+    /// Request bodies are read whole by default, whatever their size.
+    pub fn with_max_body_size(mut self, max_size: usize) -> HyperServer<T> {
+        self.max_body_size = Some(max_size);
+        self
+    }
+
+    /// Wrap the service, e.g. in a middleware `HyperService`, keeping everything else of this server
+    pub fn map_service<U: 'static + HyperService, F: FnOnce(Arc<T>) -> U>(self, f: F) -> HyperServer<U> {
+        HyperServer {
+            service: Arc::new(f(self.service)),
+            hooks: self.hooks,
+            access_log: self.access_log,
+            peer: self.peer,
+            peer_identity: self.peer_identity,
+            panic_hook: self.panic_hook,
+            error_handler: self.error_handler,
+            dev_mode: self.dev_mode,
+            concurrency_limit: self.concurrency_limit,
+            max_body_size: self.max_body_size,
+        }
+    }
+}
+
+impl<T: 'static + HyperService> Clone for HyperServer<T> {
+    fn clone(&self) -> HyperServer<T> {
+        HyperServer {
+            service: self.service.clone(),
+            hooks: self.hooks.clone(),
+            access_log: self.access_log.clone(),
+            peer: self.peer,
+            peer_identity: self.peer_identity.clone(),
+            panic_hook: self.panic_hook.clone(),
+            error_handler: self.error_handler.clone(),
+            dev_mode: self.dev_mode,
+            concurrency_limit: self.concurrency_limit.clone(),
+            max_body_size: self.max_body_size,
+        }
+    }
 }
 
 impl<T: Send + Sync + 'static + HyperService> Service<Request> for HyperServer<T> {
@@ -434,35 +1367,334 @@ impl<T: Send + Sync + 'static + HyperService> Service<Request> for HyperServer<T
     type Future = Pin<Box<dyn Future<Output=Result<Self::Response, Self::Error>>+Send>>;
 
     fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        Poll::Ready(Ok(())) // TODO
+        // A server is cloned per connection, so its `ConcurrencyLimit` is applied per request in `call` instead
+        Poll::Ready(Ok(()))
     }
 
     fn call(&mut self, req: Request) -> Self::Future {
-        if req.method() != &Method::POST {
-            Box::pin(future::ready(Ok(TwirpError::new(StatusCode::METHOD_NOT_ALLOWED, "bad_method",
-                "Method must be POST").to_hyper_resp())))
-        } else if req.headers().get(header::CONTENT_TYPE) != Some(&HeaderValue::from_static("application/protobuf")) {
-            Box::pin(future::ready(Ok(TwirpError::new(StatusCode::UNSUPPORTED_MEDIA_TYPE,
-                "bad_content_type", "Content type must be application/protobuf").to_hyper_resp())))
+        let start = Instant::now();
+        let rpc = RpcInfo::new(RpcKind::Server, req.uri().path());
+        let hooks = self.hooks.clone();
+        hooks.iter().for_each(|h| h.request_started(&rpc));
+        let finished = RpcFinished { rpc: rpc.clone(), hooks: hooks.clone() };
+        let access = self.access_log.clone().map(|log| {
+            let record = AccessLogRecord {
+                timestamp: SystemTime::now(),
+                peer: self.peer,
+                http_method: req.method().clone(),
+                rpc: rpc.clone(),
+                content_type: req.headers().get(header::CONTENT_TYPE).
+                    map(|v| String::from_utf8_lossy(v.as_bytes()).into_owned()),
+                headers: log.allowed_headers(req.headers()),
+                outcome: RpcOutcome {
+                    status: None, error_code: None, request_size: 0, response_size: 0, latency: Duration::default(),
+                },
+            };
+            (log, record)
+        });
+        let span = rpc_trace::RpcSpan::server(&rpc, req.headers());
+        let fut = span.in_scope(|| self.call_limited(&rpc, &hooks, req));
+        span.instrument(Box::pin(fut.map(move |r| {
+            let outcome = match &r {
+                Ok(served) => RpcOutcome {
+                    status: Some(served.resp.status()),
+                    error_code: served.error_code.clone(),
+                    request_size: served.request_size,
+                    response_size: served.response_size,
+                    latency: start.elapsed(),
+                },
+                Err(_) => RpcOutcome {
+                    status: None, error_code: Some("internal".to_string()), request_size: 0, response_size: 0,
+                    latency: start.elapsed(),
+                },
+            };
+            rpc_trace::record_outcome(&outcome);
+            hooks.iter().for_each(|h| h.request_completed(&rpc, &outcome));
+            if let Some((log, mut record)) = access {
+                record.outcome = outcome;
+                log.sink.log(&record);
+            }
+            drop(finished);
+            r.map(|served| served.resp)
+        })))
+    }
+}
+
+/// Calls `RpcHooks::request_finished` when dropped, be it after the request completed or with its dropped future
+struct RpcFinished {
+    rpc: RpcInfo,
+    hooks: Vec<Arc<dyn RpcHooks>>,
+}
+
+impl Drop for RpcFinished {
+    fn drop(&mut self) {
+        self.hooks.iter().for_each(|h| h.request_finished(&self.rpc));
+    }
+}
+
+/// A hyper response along with how it came about
+struct Served {
+    resp: Response,
+    response_size: usize,
+    error_code: Option<String>,
+    request_size: usize,
+}
+
+impl Served {
+    #[allow(clippy::result_large_err)]
+    fn response(resp: ServiceResponse<Vec<u8>>, request_size: usize) -> Result<Served, ProstTwirpError> {
+        // Services can also answer with a serialized Twirp error instead of failing
+        let error_code = if resp.status.is_success() {
+            None
         } else {
-            // Ug: https://github.com/tokio-rs/tokio-service/issues/9 // TODO
-            let service = self.service.clone();
-            Box::pin(ServiceRequest::from_hyper_raw(req).
-                and_then(move |v| service.handle(v)).
-                map(|r| r.map(|v| v.to_hyper_raw())).
-                map(|r| r.or_else(|err| match err.root_err() {
-                    ProstTwirpError::ProstDecodeError(_) =>
-                        Ok(TwirpError::new(StatusCode::BAD_REQUEST, "protobuf_decode_err", "Invalid protobuf body").
-                            to_hyper_resp()),
-                    ProstTwirpError::TwirpError(err) =>
-                        Ok(err.to_hyper_resp()),
-                    // Just propagate hyper errors
-                    ProstTwirpError::HyperError(err) =>
-                        Err(err),
-                    _ =>
-                        Ok(TwirpError::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_err", "Internal Error").
-                            to_hyper_resp()),
-                })))
+            Some(TwirpError::from_json_bytes(resp.status, &resp.output).
+                map_or_else(|_| "internal".to_string(), |v| v.error_type))
+        };
+        Ok(Served { resp: resp.to_hyper_raw()?, response_size: resp.output.len(), error_code, request_size })
+    }
+
+    fn error(err: TwirpError, request_size: usize) -> Served {
+        let raw = err.to_resp_raw();
+        let (resp, response_size) = match raw.to_hyper_raw() {
+            Ok(resp) => (resp, raw.output.len()),
+            // Last resort for errors that can't even be turned into a response
+            Err(_) => {
+                let mut resp = Response::new(Body::empty());
+                *resp.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+                (resp, 0)
+            },
+        };
+        Served { resp, response_size, error_code: Some(err.error_type), request_size }
+    }
+}
+
+impl<T: Send + Sync + 'static + HyperService> HyperServer<T> {
+    fn call_limited(&mut self, rpc: &RpcInfo, hooks: &[Arc<dyn RpcHooks>], req: Request)
+            -> Pin<Box<dyn Future<Output=Result<Served, hyper::Error>>+Send>> {
+        let limit = match &self.concurrency_limit {
+            Some(limit) => limit.acquire(rpc),
+            None => return self.call_inner(rpc, req),
+        };
+        let (rpc, hooks) = (rpc.clone(), hooks.to_vec());
+        // The body is only read once the request was admitted
+        let served = self.call_inner(&rpc, req);
+        Box::pin(async move {
+            let admission = limit.await;
+            for outcome in &admission.limited {
+                hooks.iter().for_each(|h| h.request_limited(&rpc, outcome));
+            }
+            match admission.permit {
+                Ok(permit) => served.map(move |v| {
+                    drop(permit);
+                    v
+                }).await,
+                Err(err) => Ok(Served::error(err, 0)),
+            }
+        })
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn call_inner(&mut self, rpc: &RpcInfo, req: Request)
+            -> Pin<Box<dyn Future<Output=Result<Served, hyper::Error>>+Send>> {
+        let ctx = ErrorContext { rpc: rpc.clone(), http_method: req.method().clone(), headers: req.headers().clone() };
+        // Ug: https://github.com/tokio-rs/tokio-service/issues/9 // TODO
+        let service = self.service.clone();
+        let error_handler = self.error_handler.clone();
+        let panic_hook = self.panic_hook.clone();
+        let dev_mode = self.dev_mode;
+        let peer_identity = self.peer_identity.clone();
+        let max_body_size = self.max_body_size;
+        Box::pin(async move {
+            let mut request_size = 0;
+            let result = if ctx.http_method != Method::POST {
+                Err(TwirpError::new(StatusCode::NOT_FOUND, "bad_route",
+                    &format!("unsupported method {} (only POST is allowed)", ctx.http_method)).into())
+            } else if !ctx.headers.get(header::CONTENT_TYPE).is_some_and(is_protobuf) {
+                let content_type = ctx.headers.get(header::CONTENT_TYPE).
+                    map_or_else(String::new, |v| String::from_utf8_lossy(v.as_bytes()).into_owned());
+                Err(TwirpError::new(StatusCode::NOT_FOUND, "bad_route",
+                    &format!("unexpected Content-Type: {:?}", content_type)).into())
+            } else {
+                // Successful responses are in the same media type as the request, e.g. for `application/x-protobuf`
+                let content_type = ctx.headers.get(header::CONTENT_TYPE).and_then(media_type).
+                    and_then(|v| HeaderValue::from_str(&v).ok());
+                match ServiceRequest::from_hyper_raw_max(req, max_body_size).await {
+                    Err(err) => Err(err),
+                    Ok(mut req) => {
+                        request_size = req.input.len();
+                        req.peer_identity = peer_identity;
+                        // Catch panics both when creating and when polling the service's future so the connection
+                        // survives
+                        let handled = match panic::catch_unwind(AssertUnwindSafe(|| service.handle(req))) {
+                            Ok(fut) => AssertUnwindSafe(fut).catch_unwind().await,
+                            Err(payload) => Err(payload),
+                        };
+                        handled.unwrap_or_else(|payload| {
+                            if let Some(hook) = &panic_hook { hook(&ctx.rpc, &*payload); }
+                            Err(TwirpError::new(StatusCode::INTERNAL_SERVER_ERROR, "internal", "Internal Error").
+                                into())
+                        }).map(|mut resp| {
+                            let keeps_codec = resp.status.is_success() &&
+                                resp.headers.get(header::CONTENT_TYPE).is_none_or(is_protobuf);
+                            if let Some(content_type) = content_type.filter(|_| keeps_codec) {
+                                resp.headers.insert(header::CONTENT_TYPE, content_type);
+                            }
+                            resp
+                        })
+                    },
+                }
+            };
+            match result.and_then(|resp| Served::response(resp, request_size)) {
+                Ok(served) => Ok(served),
+                Err(err) => {
+                    // Twirp errors without a cause have nothing to add
+                    let cause = if dev_mode && (err.twirp_error().is_none() || err.source().is_some()) {
+                        Some(err.cause_chain())
+                    } else {
+                        None
+                    };
+                    let mut err = error_handler.handle_error(&ctx, err)?;
+                    if let Some(cause) = cause {
+                        match &mut err.meta {
+                            Some(serde_json::Value::Object(meta)) => {
+                                meta.entry("cause").or_insert_with(|| serde_json::Value::String(cause));
+                            },
+                            meta @ None => *meta = Some(serde_json::json!({ "cause": cause })),
+                            // Leave meta that can't take a cause alone
+                            Some(_) => (),
+                        }
+                    }
+                    Ok(Served::error(err, request_size))
+                },
+            }
+        })
+    }
+}
+
+/// Per-RPC `tracing` spans, only recorded when the `tracing` feature is enabled
+///
+/// Spans are named `twirp.client` and `twirp.server` and carry the service, method, HTTP status code, Twirp error code,
+/// request/response body sizes and the latency. `record_outcome` writes to the current span, so it must be called
+/// inside `RpcSpan::in_scope` or a future passed to `RpcSpan::instrument`.
+///
+/// With the `opentelemetry` feature, the client span's context is also injected into the request headers and the server
+/// span continues the context extracted from them, using the globally configured text map propagator.
+#[cfg(feature = "tracing")]
+mod rpc_trace {
+    use std::future::Future;
+    use std::pin::Pin;
+    use hyper::header::HeaderMap;
+    use tracing::field::Empty;
+    use tracing::{Instrument, Span};
+    use super::{RpcInfo, RpcOutcome};
+
+    pub struct RpcSpan(Span);
+
+    impl RpcSpan {
+        pub fn client(rpc: &RpcInfo) -> RpcSpan {
+            RpcSpan(tracing::info_span!("twirp.client", otel.kind = "client", rpc.system = "twirp",
+                rpc.service = rpc.service.as_str(), rpc.method = rpc.method.as_str(), http.status_code = Empty,
+                twirp.error_code = Empty, request.size = Empty, response.size = Empty, latency_ms = Empty))
+        }
+
+        pub fn server(rpc: &RpcInfo, _headers: &HeaderMap) -> RpcSpan {
+            let span = tracing::info_span!("twirp.server", otel.kind = "server", rpc.system = "twirp",
+                rpc.service = rpc.service.as_str(), rpc.method = rpc.method.as_str(), http.status_code = Empty,
+                twirp.error_code = Empty, request.size = Empty, response.size = Empty, latency_ms = Empty);
+            #[cfg(feature = "opentelemetry")]
+            otel::set_parent(&span, _headers);
+            RpcSpan(span)
+        }
+
+        /// Put the span's trace context into the headers of an outgoing request
+        pub fn inject_context(&self, _headers: &mut HeaderMap) {
+            #[cfg(feature = "opentelemetry")]
+            otel::inject(&self.0, _headers);
+        }
+
+        pub fn in_scope<R>(&self, f: impl FnOnce() -> R) -> R { self.0.in_scope(f) }
+
+        pub fn instrument<T: 'static>(self, fut: Pin<Box<dyn Future<Output=T>+Send>>)
+                -> Pin<Box<dyn Future<Output=T>+Send>> {
+            Box::pin(fut.instrument(self.0))
+        }
+    }
+
+    pub fn record_outcome(outcome: &RpcOutcome) {
+        let span = Span::current();
+        if let Some(status) = outcome.status { span.record("http.status_code", status.as_u16()); }
+        if let Some(error_code) = &outcome.error_code { span.record("twirp.error_code", error_code.as_str()); }
+        span.record("request.size", outcome.request_size as u64);
+        span.record("response.size", outcome.response_size as u64);
+        span.record("latency_ms", outcome.latency.as_secs_f64() * 1000.0);
+    }
+
+    /// W3C trace context propagation through request headers
+    #[cfg(feature = "opentelemetry")]
+    mod otel {
+        use hyper::header::{HeaderMap, HeaderName, HeaderValue};
+        use opentelemetry::global;
+        use opentelemetry::propagation::{Extractor, Injector};
+        use tracing::Span;
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+        struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+        impl Injector for HeaderInjector<'_> {
+            fn set(&mut self, key: &str, value: String) {
+                // Invalid values are not worth failing the request over, the context just doesn't propagate
+                if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(key.as_bytes()), HeaderValue::from_str(&value)) {
+                    self.0.insert(name, value);
+                }
+            }
+        }
+
+        struct HeaderExtractor<'a>(&'a HeaderMap);
+
+        impl Extractor for HeaderExtractor<'_> {
+            fn get(&self, key: &str) -> Option<&str> { self.0.get(key).and_then(|v| v.to_str().ok()) }
+
+            fn keys(&self) -> Vec<&str> { self.0.keys().map(|k| k.as_str()).collect() }
+        }
+
+        pub fn inject(span: &Span, headers: &mut HeaderMap) {
+            let context = span.context();
+            global::get_text_map_propagator(|propagator| {
+                propagator.inject_context(&context, &mut HeaderInjector(headers))
+            });
+        }
+
+        pub fn set_parent(span: &Span, headers: &HeaderMap) {
+            let context = global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)));
+            let _ = span.set_parent(context);
         }
     }
 }
+
+/// No-op stand-ins for when the `tracing` feature is disabled
+#[cfg(not(feature = "tracing"))]
+mod rpc_trace {
+    use std::future::Future;
+    use std::pin::Pin;
+    use hyper::header::HeaderMap;
+    use super::{RpcInfo, RpcOutcome};
+
+    pub struct RpcSpan;
+
+    impl RpcSpan {
+        pub fn client(_rpc: &RpcInfo) -> RpcSpan { RpcSpan }
+
+        pub fn server(_rpc: &RpcInfo, _headers: &HeaderMap) -> RpcSpan { RpcSpan }
+
+        pub fn inject_context(&self, _headers: &mut HeaderMap) {}
+
+        pub fn in_scope<R>(&self, f: impl FnOnce() -> R) -> R { f() }
+
+        pub fn instrument<T: 'static>(self, fut: Pin<Box<dyn Future<Output=T>+Send>>)
+                -> Pin<Box<dyn Future<Output=T>+Send>> {
+            fut
+        }
+    }
+
+    pub fn record_outcome(_outcome: &RpcOutcome) {}
+}
@@ -0,0 +1,202 @@
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use futures_util::stream::{FuturesUnordered, Stream};
+use hyper::client::HttpConnector;
+use hyper::server::accept::Accept;
+use hyper::server::conn::{AddrIncoming, AddrStream};
+use rustls::{Certificate, ClientConfig, PrivateKey, RootCertStore, ServerConfig};
+use rustls::server::AllowAnyAuthenticatedClient;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::server::TlsStream;
+use crate::PeerIdentity;
+
+/// The connector of `HyperClient`s calling servers over HTTPS, see `https_connector`
+pub type HttpsConnector = hyper_rustls::HttpsConnector<HttpConnector>;
+
+/// A connector for `HyperClient`s calling `https://` root URLs with the given config, see `tls_client_config`
+///
+/// HTTP/2 is offered with ALPN when the `http2` feature is enabled, see `Http2::Alpn`.
+pub fn https_connector(config: ClientConfig) -> HttpsConnector {
+    let builder = hyper_rustls::HttpsConnectorBuilder::new().with_tls_config(config).https_only();
+    #[cfg(feature = "http2")]
+    let connector = builder.enable_all_versions().build();
+    #[cfg(not(feature = "http2"))]
+    let connector = builder.enable_http1().build();
+    connector
+}
+
+/// A rustls client config trusting servers with certificates signed by the given roots
+///
+/// The certificate chain and key are presented to servers asking for client certificates, as is done for mutual TLS.
+pub fn tls_client_config(roots: RootCertStore, identity: Option<(Vec<Certificate>, PrivateKey)>)
+        -> Result<ClientConfig, rustls::Error> {
+    let builder = ClientConfig::builder().with_safe_defaults().with_root_certificates(roots);
+    match identity {
+        Some((cert_chain, key)) => builder.with_client_auth_cert(cert_chain, key),
+        None => Ok(builder.with_no_client_auth()),
+    }
+}
+
+/// A rustls server config presenting the given certificate chain and key
+///
+/// With `client_roots`, clients must present a certificate signed by one of them, as is done for mutual TLS. The ALPN
+/// protocols include HTTP/2 when the `http2` feature is enabled.
+pub fn tls_server_config(cert_chain: Vec<Certificate>, key: PrivateKey, client_roots: Option<RootCertStore>)
+        -> Result<ServerConfig, rustls::Error> {
+    let builder = ServerConfig::builder().with_safe_defaults();
+    let builder = match client_roots {
+        Some(roots) => builder.with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots).boxed()),
+        None => builder.with_no_client_auth(),
+    };
+    let mut config = builder.with_single_cert(cert_chain, key)?;
+    if cfg!(feature = "http2") {
+        config.alpn_protocols.push(b"h2".to_vec());
+    }
+    config.alpn_protocols.push(b"http/1.1".to_vec());
+    Ok(config)
+}
+
+/// Every certificate in the given PEM data
+pub fn load_certificates(pem: &[u8]) -> io::Result<Vec<Certificate>> {
+    Ok(rustls_pemfile::certs(&mut &*pem)?.into_iter().map(Certificate).collect())
+}
+
+/// The first PKCS#8, PKCS#1 or SEC1 private key in the given PEM data
+pub fn load_private_key(pem: &[u8]) -> io::Result<PrivateKey> {
+    let mut reader = pem;
+    loop {
+        match rustls_pemfile::read_one(&mut reader)? {
+            Some(rustls_pemfile::Item::PKCS8Key(key)) | Some(rustls_pemfile::Item::RSAKey(key)) |
+                Some(rustls_pemfile::Item::ECKey(key)) => return Ok(PrivateKey(key)),
+            Some(_) => {},
+            None => return Err(io::Error::new(io::ErrorKind::InvalidData, "no private key found")),
+        }
+    }
+}
+
+type Handshake = Pin<Box<dyn Future<Output=Option<TlsConn>> + Send>>;
+
+/// A hyper acceptor of TLS connections, to serve a `HyperServer` or `ConnectServer` with `Server::builder`
+///
+/// Handshakes run concurrently and are done by the time `make_service_fn` sees the `TlsConn`, so the identity of the
+/// client is known there. Connections failing or not finishing the handshake in time are dropped. While the most
+/// handshakes allowed are running, new connections wait in the listen backlog.
+pub struct TlsIncoming {
+    incoming: AddrIncoming,
+    acceptor: TlsAcceptor,
+    handshake_timeout: Duration,
+    max_handshakes: usize,
+    handshakes: FuturesUnordered<Handshake>,
+}
+
+impl TlsIncoming {
+    /// Accept TLS connections on the given address with the given config, see `tls_server_config`
+    pub fn bind(addr: &SocketAddr, config: ServerConfig) -> Result<TlsIncoming, hyper::Error> {
+        Ok(TlsIncoming::new(AddrIncoming::bind(addr)?, config))
+    }
+
+    /// Accept TLS connections on the given TCP connections with the given config
+    pub fn new(incoming: AddrIncoming, config: ServerConfig) -> TlsIncoming {
+        TlsIncoming {
+            incoming,
+            acceptor: TlsAcceptor::from(Arc::new(config)),
+            handshake_timeout: Duration::from_secs(10),
+            max_handshakes: 1024,
+            handshakes: FuturesUnordered::new(),
+        }
+    }
+
+    /// Set how long clients have to finish the handshake, 10 seconds by default
+    pub fn with_handshake_timeout(mut self, handshake_timeout: Duration) -> TlsIncoming {
+        self.handshake_timeout = handshake_timeout;
+        self
+    }
+
+    /// Set how many handshakes may run at once, 1024 by default and at least 1
+    pub fn with_max_handshakes(mut self, max_handshakes: usize) -> TlsIncoming {
+        self.max_handshakes = max_handshakes.max(1);
+        self
+    }
+
+    /// The local address connections are accepted on
+    pub fn local_addr(&self) -> SocketAddr { self.incoming.local_addr() }
+}
+
+impl Accept for TlsIncoming {
+    type Conn = TlsConn;
+    type Error = io::Error;
+
+    fn poll_accept(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<TlsConn, io::Error>>> {
+        let this = self.get_mut();
+        loop {
+            // Start the handshake of new connections while there is room, hyper only stops serving on errors of the
+            // listener itself
+            while this.handshakes.len() < this.max_handshakes {
+                match Pin::new(&mut this.incoming).poll_accept(cx) {
+                    Poll::Ready(Some(Ok(stream))) => {
+                        let handshake = tokio::time::timeout(this.handshake_timeout, this.acceptor.accept(stream));
+                        this.handshakes.push(Box::pin(async move { handshake.await.ok()?.ok().map(TlsConn) }));
+                    },
+                    Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                    Poll::Ready(None) => return Poll::Ready(None),
+                    Poll::Pending => break,
+                }
+            }
+            // A failed handshake makes room for the next connection
+            match Pin::new(&mut this.handshakes).poll_next(cx) {
+                Poll::Ready(Some(Some(conn))) => return Poll::Ready(Some(Ok(conn))),
+                Poll::Ready(Some(None)) => continue,
+                Poll::Ready(None) | Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// A TLS connection accepted by `TlsIncoming`
+pub struct TlsConn(TlsStream<AddrStream>);
+
+impl TlsConn {
+    /// The remote address of the connection, e.g. for `HyperServer::with_peer`
+    pub fn remote_addr(&self) -> SocketAddr { self.0.get_ref().0.remote_addr() }
+
+    /// The identity the client proved with its certificate, if the server asked for one
+    ///
+    /// Pass it on to `HyperServer::with_peer_identity` for the services to see it.
+    pub fn peer_identity(&self) -> Option<PeerIdentity> {
+        self.0.get_ref().1.peer_certificates().
+            map(|certs| PeerIdentity { certificates: certs.iter().map(|cert| cert.0.clone()).collect() })
+    }
+}
+
+impl AsyncRead for TlsConn {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for TlsConn {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(mut self: Pin<&mut Self>, cx: &mut Context<'_>, bufs: &[io::IoSlice<'_>])
+            -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool { self.0.is_write_vectored() }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,181 @@
+use std::convert::Infallible;
+use std::fs;
+use std::future::Future;
+use std::io;
+use std::os::unix::fs::FileTypeExt;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use futures_util::ready;
+use hyper::{Body, Client, Request, Response, Server, Uri};
+use hyper::client::connect::{Connected, Connection};
+use hyper::server::accept::Accept;
+use hyper::service::{make_service_fn, Service};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::time::Sleep;
+use crate::HyperClient;
+
+/// Serve the given service, e.g. a `HyperServer` or `ConnectServer`, on a Unix socket at the given path
+///
+/// The socket is bound right away, see `UnixIncoming::bind`, and served until the returned future is dropped.
+pub async fn serve_unix<S>(path: &Path, service: S) -> io::Result<impl Future<Output=Result<(), hyper::Error>>>
+        where S: Service<Request<Body>, Response=Response<Body>, Error=hyper::Error> + Clone + Send + 'static,
+            S::Future: Send + 'static {
+    let incoming = UnixIncoming::bind(path).await?;
+    let make_service = make_service_fn(move |_conn: &UnixStream| {
+        let service = service.clone();
+        async { Ok::<_, Infallible>(service) }
+    });
+    Ok(Server::builder(incoming).serve(make_service))
+}
+
+/// How long `UnixIncoming` waits after an error accepting connections, e.g. when out of file descriptors
+const ACCEPT_ERROR_BACKOFF: Duration = Duration::from_secs(1);
+
+/// A hyper acceptor of connections on a Unix socket, to serve with `Server::builder`
+///
+/// Like hyper's `AddrIncoming`, errors accepting a connection don't stop the server: connections that went away are
+/// skipped and on other errors, e.g. running out of file descriptors, accepting is retried after a second. The socket
+/// file is removed when this is dropped.
+pub struct UnixIncoming {
+    listener: UnixListener,
+    path: PathBuf,
+    backoff: Option<Pin<Box<Sleep>>>,
+}
+
+impl UnixIncoming {
+    /// Accept connections on a Unix socket at the given path
+    ///
+    /// A socket left over at the path, e.g. by a process that didn't shut down cleanly, is replaced. A socket that is
+    /// still listened on is an `AddrInUse` error, as is any other file there.
+    pub async fn bind(path: impl AsRef<Path>) -> io::Result<UnixIncoming> {
+        let path = path.as_ref().to_path_buf();
+        if fs::symlink_metadata(&path).is_ok_and(|v| v.file_type().is_socket()) {
+            // Only a socket nobody listens on anymore refuses connections
+            match UnixStream::connect(&path).await {
+                Err(err) if err.kind() == io::ErrorKind::ConnectionRefused => fs::remove_file(&path)?,
+                _ => return Err(io::Error::new(io::ErrorKind::AddrInUse,
+                    format!("{} is still listened on", path.display()))),
+            }
+        }
+        Ok(UnixIncoming { listener: UnixListener::bind(&path)?, path, backoff: None })
+    }
+
+    /// The path of the socket
+    pub fn path(&self) -> &Path { &self.path }
+}
+
+impl Accept for UnixIncoming {
+    type Conn = UnixStream;
+    type Error = io::Error;
+
+    fn poll_accept(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<UnixStream, io::Error>>> {
+        if let Some(backoff) = &mut self.backoff {
+            ready!(backoff.as_mut().poll(cx));
+            self.backoff = None;
+        }
+        loop {
+            let err = match ready!(self.listener.poll_accept(cx)) {
+                Ok((stream, _)) => return Poll::Ready(Some(Ok(stream))),
+                Err(err) => err,
+            };
+            match err.kind() {
+                // The connection went away before it was accepted
+                io::ErrorKind::ConnectionAborted | io::ErrorKind::ConnectionReset | io::ErrorKind::Interrupted => (),
+                // The listener itself is broken, which waiting won't fix
+                io::ErrorKind::InvalidInput | io::ErrorKind::Unsupported => return Poll::Ready(Some(Err(err))),
+                _ => {
+                    #[cfg(feature = "log")]
+                    log::error!("accept error on {}: {}", self.path.display(), err);
+                    #[cfg(feature = "tracing")]
+                    tracing::error!(path = %self.path.display(), error = %err, "accept error");
+                    let mut backoff = Box::pin(tokio::time::sleep(ACCEPT_ERROR_BACKOFF));
+                    if backoff.as_mut().poll(cx).is_pending() {
+                        self.backoff = Some(backoff);
+                        return Poll::Pending;
+                    }
+                },
+            }
+        }
+    }
+}
+
+impl Drop for UnixIncoming {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// A connector for `HyperClient`s calling a server on a Unix socket, whatever the host of the root URL
+///
+/// See `HyperClient::new_unix`.
+#[derive(Debug, Clone)]
+pub struct UnixConnector {
+    path: Arc<PathBuf>,
+}
+
+impl UnixConnector {
+    /// Connect to the Unix socket at the given path
+    pub fn new(path: impl Into<PathBuf>) -> UnixConnector {
+        UnixConnector { path: Arc::new(path.into()) }
+    }
+}
+
+impl Service<Uri> for UnixConnector {
+    type Response = UnixConn;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output=io::Result<UnixConn>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<io::Result<()>> { Poll::Ready(Ok(())) }
+
+    fn call(&mut self, _uri: Uri) -> Self::Future {
+        let path = self.path.clone();
+        Box::pin(async move { UnixStream::connect(&*path).await.map(UnixConn) })
+    }
+}
+
+/// A client connection of a `UnixConnector`
+pub struct UnixConn(UnixStream);
+
+impl Connection for UnixConn {
+    fn connected(&self) -> Connected { Connected::new() }
+}
+
+impl AsyncRead for UnixConn {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for UnixConn {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(mut self: Pin<&mut Self>, cx: &mut Context<'_>, bufs: &[io::IoSlice<'_>])
+            -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool { self.0.is_write_vectored() }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
+
+impl HyperClient<UnixConnector> {
+    /// Create a new client wrapper calling the server on the Unix socket at the given path using protobuf
+    ///
+    /// Calls go to the usual Twirp routes, with `localhost` as the host.
+    pub fn new_unix(path: impl Into<PathBuf>) -> HyperClient<UnixConnector> {
+        HyperClient::new(Client::builder().build(UnixConnector::new(path)), "http://localhost")
+    }
+}
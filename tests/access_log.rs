@@ -0,0 +1,92 @@
+#![allow(clippy::result_large_err)]
+
+mod common;
+
+use std::io::{self, Write};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, UNIX_EPOCH};
+
+use common::{serve, Upper};
+use hyper::{Method, StatusCode};
+use hyper::header::{HeaderName, HeaderValue, AUTHORIZATION};
+use prost_twirp::{AccessLog, AccessLogRecord, HyperServer, JsonLinesSink, RpcInfo, RpcKind, RpcOutcome, ServiceRequest,
+    ServiceResponse};
+use serde_json::{json, Value};
+
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> { self.0.lock().unwrap().write(buf) }
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+impl Buffer {
+    fn records(&self) -> Vec<Value> {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap().lines().
+            map(|line| serde_json::from_str(line).unwrap()).collect()
+    }
+}
+
+#[tokio::test]
+async fn logs_one_record_per_request() {
+    let buffer = Buffer::default();
+    let access_log = AccessLog::new(JsonLinesSink::new(buffer.clone())).
+        with_header(HeaderName::from_static("x-request-id"));
+    let client = serve(move |conn| {
+        HyperServer::new(Upper).with_access_log(access_log.clone()).with_peer(conn.remote_addr())
+    });
+
+    let mut req = ServiceRequest::new("hello".to_string());
+    req.headers.insert("x-request-id", HeaderValue::from_static("abc"));
+    req.headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer secret"));
+    let res: ServiceResponse<String> = client.go("/twirp/test.Strings/Upper", req).await.unwrap();
+    assert_eq!(res.output, "HELLO");
+    client.go::<_, String>("/twirp/test.Strings/Lower", ServiceRequest::new("hello".to_string())).await.unwrap_err();
+
+    let records = buffer.records();
+    assert_eq!(records.len(), 2);
+    let ok = &records[0];
+    assert!(ok["peer"].as_str().unwrap().starts_with("127.0.0.1:"));
+    assert_eq!(ok["http_method"], "POST");
+    assert_eq!(ok["path"], "/twirp/test.Strings/Upper");
+    assert_eq!(ok["service"], "test.Strings");
+    assert_eq!(ok["method"], "Upper");
+    assert_eq!(ok["content_type"], "application/protobuf");
+    assert_eq!(ok["request_size"], 7);
+    assert_eq!(ok["response_size"], 7);
+    assert_eq!(ok["status"], 200);
+    assert_eq!(ok["twirp_code"], "ok");
+    assert!(ok["duration_ms"].is_f64());
+    assert_eq!(ok["headers"], json!({ "x-request-id": "abc" }));
+    assert!(!buffer.records().iter().any(|r| r.to_string().contains("secret")));
+
+    let not_found = &records[1];
+    assert_eq!(not_found["status"], 404);
+    assert_eq!(not_found["twirp_code"], "not_found");
+    assert_eq!(not_found["headers"], json!({}));
+}
+
+#[test]
+fn renders_record() {
+    let record = AccessLogRecord {
+        timestamp: UNIX_EPOCH + Duration::from_millis(951_782_400_123),
+        peer: Some(SocketAddr::from(([10, 0, 0, 1], 1234))),
+        http_method: Method::POST,
+        rpc: RpcInfo::new(RpcKind::Server, "/twirp/test.Strings/Upper"),
+        content_type: Some("application/protobuf".to_string()),
+        headers: vec![("x-request-id".to_string(), "abc".to_string())],
+        outcome: RpcOutcome {
+            status: Some(StatusCode::INTERNAL_SERVER_ERROR),
+            error_code: Some("internal".to_string()),
+            request_size: 3,
+            response_size: 45,
+            latency: Duration::from_micros(1500),
+        },
+    };
+    assert_eq!(record.to_json()["timestamp"], "2000-02-29T00:00:00.123Z");
+    assert_eq!(record.to_string(), "timestamp=2000-02-29T00:00:00.123Z peer=10.0.0.1:1234 http_method=POST \
+        path=\"/twirp/test.Strings/Upper\" content_type=\"application/protobuf\" request_size=3 response_size=45 \
+        status=500 twirp_code=internal duration_ms=1.500 header.x-request-id=\"abc\"");
+}
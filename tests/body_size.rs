@@ -0,0 +1,59 @@
+#![allow(clippy::result_large_err)]
+
+mod common;
+
+use std::time::Duration;
+
+use common::{serve, Echo};
+use hyper::StatusCode;
+use prost_twirp::{HyperServer, ProstTwirpError, ServiceRequest, TwirpError};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+fn twirp_error(err: &ProstTwirpError) -> &TwirpError {
+    err.twirp_error().unwrap_or_else(|| panic!("expected a Twirp error, got {:?}", err))
+}
+
+#[tokio::test]
+async fn server_rejects_large_request_bodies() {
+    let client = serve(|_| HyperServer::new(Echo::default()).with_max_body_size(16));
+    let resp = client.go::<_, String>("/twirp/test.Echo/Echo", ServiceRequest::new("hello".to_string())).await;
+    assert_eq!(resp.unwrap().output, "hello");
+
+    // Known to be too large by its Content-Length
+    let err = client.go::<_, String>("/twirp/test.Echo/Echo", ServiceRequest::new("x".repeat(100))).await.
+        unwrap_err();
+    let err = twirp_error(&err);
+    assert_eq!((err.status, err.error_type.as_str()), (StatusCode::BAD_REQUEST, "malformed"));
+    assert_eq!(err.msg, "the body is larger than the maximum of 16 bytes");
+
+    // Streamed in chunks without an end, so it is only answered if it stops being read at the limit
+    let addr = client.root_url.trim_start_matches("http://");
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let head = format!("POST /twirp/test.Echo/Echo HTTP/1.1\r\nhost: {}\r\ncontent-type: application/protobuf\r\n\
+        transfer-encoding: chunked\r\n\r\n", addr);
+    stream.write_all(head.as_bytes()).await.unwrap();
+    for _ in 0..4 {
+        stream.write_all(b"8\r\nxxxxxxxx\r\n").await.unwrap();
+    }
+    let mut resp = Vec::new();
+    let read = tokio::time::timeout(Duration::from_secs(5), async {
+        while !resp.ends_with(b"}") && stream.read_buf(&mut resp).await.unwrap() > 0 {}
+    });
+    read.await.expect("the request body was read past the limit");
+    let resp = String::from_utf8(resp).unwrap();
+    assert!(resp.starts_with("HTTP/1.1 400 Bad Request\r\n"), "{}", resp);
+    let body = r#"{"code":"malformed","msg":"the body is larger than the maximum of 16 bytes"}"#;
+    assert!(resp.ends_with(body), "{}", resp);
+}
+
+#[tokio::test]
+async fn client_rejects_large_response_bodies() {
+    let client = serve(|_| HyperServer::new(Echo::default())).with_max_body_size(16);
+    let resp = client.go::<_, String>("/twirp/test.Echo/Echo", ServiceRequest::new("hello".to_string())).await;
+    assert_eq!(resp.unwrap().output, "hello");
+
+    let err = client.go::<_, String>("/twirp/test.Echo/Echo", ServiceRequest::new("x".repeat(100))).await.
+        unwrap_err();
+    assert_eq!(twirp_error(&err).error_type, "malformed");
+}
@@ -0,0 +1,96 @@
+//! Fixtures shared by the integration tests, not every test uses all of them
+
+#![allow(dead_code)]
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::future;
+use hyper::{Body, Client, Method, Request, Response, Server, StatusCode};
+use hyper::server::Builder;
+use hyper::server::conn::{AddrIncoming, AddrStream};
+use hyper::service::{make_service_fn, Service};
+use prost_twirp::{HyperClient, HyperService, PTRes, ServiceDescriptor, ServiceRequest, ServiceResponse, TwirpError};
+use tokio::sync::Notify;
+
+pub static STRINGS: ServiceDescriptor = ServiceDescriptor {
+    package: "test", name: "Strings", full_name: "test.Strings", methods: &[],
+};
+
+pub static ECHO: ServiceDescriptor = ServiceDescriptor {
+    package: "test", name: "Echo", full_name: "test.Echo", methods: &[],
+};
+
+/// Uppercases the input of `/twirp/test.Strings/Upper`, answering every other path with a `not_found` error
+pub struct Upper;
+impl HyperService for Upper {
+    fn handle(&self, req: ServiceRequest<Vec<u8>>) -> PTRes<Vec<u8>> {
+        match (req.method.clone(), req.uri.path()) {
+            (Method::POST, "/twirp/test.Strings/Upper") =>
+                Box::pin(future::ready(req.to_proto().and_then(|req: ServiceRequest<String>| {
+                    ServiceResponse::new(req.input.to_uppercase()).to_proto_raw()
+                }))),
+            _ => Box::pin(future::ok(TwirpError::new(StatusCode::NOT_FOUND, "not_found", "Not found").to_resp_raw()))
+        }
+    }
+
+    fn descriptor(&self) -> Option<&'static ServiceDescriptor> { Some(&STRINGS) }
+}
+
+/// Echoes with `/twirp/test.Echo/Echo` and sleeps for as many milliseconds as asked for with `/twirp/test.Echo/Sleep`,
+/// telling `entered` about it
+///
+/// Inputs like `fail with <code>` fail with that code instead, other paths are answered with a `bad_route` error.
+#[derive(Clone, Default)]
+pub struct Echo {
+    pub entered: Arc<Notify>,
+}
+
+impl HyperService for Echo {
+    fn handle(&self, req: ServiceRequest<Vec<u8>>) -> PTRes<Vec<u8>> {
+        let path = req.uri.path().to_string();
+        if path != "/twirp/test.Echo/Echo" && path != "/twirp/test.Echo/Sleep" {
+            return Box::pin(future::err(TwirpError::new(StatusCode::NOT_FOUND, "bad_route",
+                &format!("no handler for path {:?}", path)).into()));
+        }
+        let entered = self.entered.clone();
+        Box::pin(async move {
+            let req: ServiceRequest<String> = req.to_proto()?;
+            if path == "/twirp/test.Echo/Sleep" {
+                entered.notify_one();
+                tokio::time::sleep(Duration::from_millis(req.input.parse().unwrap())).await;
+            }
+            if let Some(code) = req.input.strip_prefix("fail with ") {
+                return Err(TwirpError::new(StatusCode::BAD_REQUEST, code, "failed on purpose").
+                    with_meta_str("key", "value").into());
+            }
+            ServiceResponse::new(req.input).to_proto_raw()
+        })
+    }
+
+    fn descriptor(&self) -> Option<&'static ServiceDescriptor> { Some(&ECHO) }
+}
+
+/// Serve the service `make` creates for every connection, e.g. a `HyperServer`, on a free local port, returning a
+/// client calling it
+pub fn serve<S, F>(make: F) -> HyperClient
+        where S: Service<Request<Body>, Response=Response<Body>, Error=hyper::Error> + Send + 'static,
+            S::Future: Send + 'static, F: Fn(&AddrStream) -> S + Send + 'static {
+    serve_with(|builder| builder, make)
+}
+
+/// Like `serve`, with the hyper server builder set up by `configure` first
+pub fn serve_with<S, F>(configure: impl FnOnce(Builder<AddrIncoming>) -> Builder<AddrIncoming>, make: F) -> HyperClient
+        where S: Service<Request<Body>, Response=Response<Body>, Error=hyper::Error> + Send + 'static,
+            S::Future: Send + 'static, F: Fn(&AddrStream) -> S + Send + 'static {
+    let make_service = make_service_fn(move |conn: &AddrStream| {
+        let server = make(conn);
+        async { Ok::<_, Infallible>(server) }
+    });
+    let server = configure(Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0)))).serve(make_service);
+    let client = HyperClient::new(Client::new(), &format!("http://{}", server.local_addr()));
+    tokio::spawn(server);
+    client
+}
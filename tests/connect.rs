@@ -0,0 +1,104 @@
+#![allow(clippy::result_large_err)]
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::{serve, Echo};
+use hyper::{Body, Client, Method, Request, StatusCode};
+use prost_twirp::{ConnectServer, HyperClient, HyperServer, Protocol, ServiceRequest, TwirpMetrics};
+use serde_json::{json, Value};
+
+async fn raw_call(root_url: &str, method: Method, path: &str, headers: &[(&str, &str)])
+        -> (StatusCode, hyper::HeaderMap, Vec<u8>) {
+    let mut req = Request::builder().method(method).uri(format!("{}{}", root_url, path));
+    for (name, value) in headers {
+        req = req.header(*name, *value);
+    }
+    let resp = Client::new().request(req.body(Body::empty()).unwrap()).await.unwrap();
+    let (parts, body) = resp.into_parts();
+    (parts.status, parts.headers, hyper::body::to_bytes(body).await.unwrap().to_vec())
+}
+
+#[tokio::test]
+async fn serves_twirp_and_connect() {
+    let root_url = serve(|_| ConnectServer::new(HyperServer::new(Echo::default()))).root_url;
+    for protocol in &[Protocol::Twirp, Protocol::Connect] {
+        let client = HyperClient::new(Client::new(), &root_url).with_protocol(*protocol);
+        let resp = client.go::<_, String>("/twirp/test.Echo/Echo", ServiceRequest::new("hello".to_string())).await.
+            unwrap();
+        assert_eq!(resp.output, "hello");
+    }
+
+    let (status, headers, _) = raw_call(&root_url, Method::POST, "/test.Echo/Echo",
+        &[("content-type", "application/proto"), ("connect-protocol-version", "1")]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers["content-type"], "application/proto");
+}
+
+#[tokio::test]
+async fn maps_error_codes() {
+    let client = serve(|_| ConnectServer::new(HyperServer::new(Echo::default()))).with_protocol(Protocol::Connect);
+    let cases = [
+        ("not_found", "not_found", StatusCode::NOT_FOUND),
+        ("malformed", "invalid_argument", StatusCode::BAD_REQUEST),
+        ("dataloss", "dataloss", StatusCode::INTERNAL_SERVER_ERROR),
+        ("unavailable", "unavailable", StatusCode::SERVICE_UNAVAILABLE),
+        ("my_custom_code", "unknown", StatusCode::INTERNAL_SERVER_ERROR),
+    ];
+    for (code, expected, status) in &cases {
+        let err = client.go::<_, String>("/twirp/test.Echo/Echo", ServiceRequest::new(format!("fail with {}", code))).
+            await.unwrap_err();
+        let err = err.twirp_error().unwrap();
+        assert_eq!((err.error_type.as_str(), err.status, err.msg.as_str()), (*expected, *status, "failed on purpose"));
+        // Connect errors have no meta
+        assert_eq!(err.meta, None);
+    }
+
+    let (status, headers, body) = raw_call(&client.root_url, Method::POST, "/test.Echo/Missing",
+        &[("content-type", "application/proto")]).await;
+    assert_eq!(status, StatusCode::NOT_IMPLEMENTED);
+    assert_eq!(headers["content-type"], "application/json");
+    assert_eq!(serde_json::from_slice::<Value>(&body).unwrap(),
+        json!({ "code": "unimplemented", "message": "no handler for path \"/twirp/test.Echo/Missing\"" }));
+}
+
+#[tokio::test]
+async fn rejects_invalid_connect_requests() {
+    let root_url = serve(|_| ConnectServer::new(HyperServer::new(Echo::default()))).root_url;
+
+    let (status, headers, _) = raw_call(&root_url, Method::GET, "/test.Echo/Echo", &[]).await;
+    assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(headers["allow"], "POST");
+
+    let (status, headers, _) = raw_call(&root_url, Method::POST, "/test.Echo/Echo",
+        &[("content-type", "application/json")]).await;
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(headers["accept-post"], "application/proto");
+
+    let (status, _, body) = raw_call(&root_url, Method::POST, "/test.Echo/Echo",
+        &[("content-type", "application/proto"), ("connect-protocol-version", "2")]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(serde_json::from_slice::<Value>(&body).unwrap(),
+        json!({ "code": "invalid_argument", "message": "connect-protocol-version must be \"1\": got \"2\"" }));
+
+    let (status, _, body) = raw_call(&root_url, Method::POST, "/test.Echo/Echo",
+        &[("content-type", "application/proto"), ("connect-timeout-ms", "soon")]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(serde_json::from_slice::<Value>(&body).unwrap()["code"], "invalid_argument");
+}
+
+#[tokio::test]
+async fn enforces_connect_timeouts() {
+    let metrics = Arc::new(TwirpMetrics::new());
+    let server = HyperServer::new(Echo::default()).with_hooks(metrics.clone());
+    let client = serve(move |_| ConnectServer::new(server.clone())).with_protocol(Protocol::Connect).
+        with_timeout(Duration::from_millis(10));
+    let err = client.go::<_, String>("/twirp/test.Echo/Sleep", ServiceRequest::new("5000".to_string())).await.
+        unwrap_err();
+    let err = err.twirp_error().unwrap();
+    assert_eq!((err.error_type.as_str(), err.status), ("deadline_exceeded", StatusCode::GATEWAY_TIMEOUT));
+    assert!(metrics.render().contains("twirp_server_responses_total{twirp_service=\"test.Echo\",\
+        twirp_method=\"Sleep\",twirp_code=\"deadline_exceeded\"} 1"));
+}
@@ -0,0 +1,26 @@
+use hyper::header::HeaderValue;
+use prost_twirp::{is_protobuf, media_type};
+
+#[test]
+fn parses_media_types() {
+    let cases = [
+        ("application/protobuf", Some("application/protobuf")),
+        ("Application/Protobuf ; charset=binary", Some("application/protobuf")),
+        (" application/json;charset=utf-8", Some("application/json")),
+        ("application", None),
+        ("application/", None),
+        ("application/x/protobuf", None),
+        ("", None),
+    ];
+    for (value, expected) in &cases {
+        assert_eq!(media_type(&HeaderValue::from_static(value)).as_deref(), *expected, "for {:?}", value);
+    }
+}
+
+#[test]
+fn recognizes_protobuf() {
+    assert!(is_protobuf(&HeaderValue::from_static("application/protobuf")));
+    assert!(is_protobuf(&HeaderValue::from_static("application/x-protobuf; charset=binary")));
+    assert!(!is_protobuf(&HeaderValue::from_static("application/json")));
+    assert!(!is_protobuf(&HeaderValue::from_static("application/protobufx")));
+}
@@ -0,0 +1,71 @@
+#![allow(clippy::result_large_err)]
+
+use hyper::{Client, StatusCode, Uri};
+use hyper::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_LENGTH};
+use proptest::prelude::*;
+use prost_twirp::{HyperClient, ServiceRequest, ServiceResponse, TwirpError};
+
+/// Any header map that can be built, from arbitrary names and values
+fn header_map() -> impl Strategy<Value = HeaderMap> {
+    prop::collection::vec((prop::collection::vec(any::<u8>(), 0..20), prop::collection::vec(any::<u8>(), 0..40)), 0..8).
+        prop_map(|pairs| {
+            let mut headers = HeaderMap::new();
+            for (name, value) in pairs {
+                if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(&name), HeaderValue::from_bytes(&value)) {
+                    headers.append(name, value);
+                }
+            }
+            headers
+        })
+}
+
+/// Arbitrary strings, biased towards the characters of URIs
+fn uri_like() -> impl Strategy<Value = String> {
+    prop_oneof![any::<String>(), "[a-z:/?#@%\\[\\]. 0-9-]{0,40}"]
+}
+
+proptest! {
+    #[test]
+    fn request_conversion_never_panics(uri in uri_like(), headers in header_map(), input: Vec<u8>) {
+        let mut req = ServiceRequest::new(input.clone());
+        if let Ok(uri) = uri.parse::<Uri>() { req.uri = uri; }
+        req.headers = headers.clone();
+        let hyper_req = req.to_hyper_raw().unwrap();
+        prop_assert_eq!(hyper_req.uri(), &req.uri);
+        prop_assert_eq!(&hyper_req.headers()[CONTENT_LENGTH], &HeaderValue::from(input.len()));
+        for name in headers.keys().filter(|name| *name != CONTENT_LENGTH) {
+            prop_assert_eq!(hyper_req.headers().get_all(name).iter().collect::<Vec<_>>(),
+                headers.get_all(name).iter().collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn response_conversion_never_panics(status in 100u16..1000, headers in header_map(), output: Vec<u8>) {
+        let mut resp = ServiceResponse::new(output.clone());
+        resp.status = StatusCode::from_u16(status).unwrap();
+        resp.headers = headers;
+        let hyper_resp = resp.to_hyper_raw().unwrap();
+        prop_assert_eq!(hyper_resp.status(), resp.status);
+        prop_assert_eq!(&hyper_resp.headers()[CONTENT_LENGTH], &HeaderValue::from(output.len()));
+    }
+
+    #[test]
+    fn error_conversion_never_panics(status in 100u16..1000, code: String, msg: String) {
+        let err = TwirpError::new(StatusCode::from_u16(status).unwrap(), &code, &msg);
+        let resp = err.to_hyper_resp().unwrap();
+        prop_assert_eq!(resp.status(), err.status);
+        let parsed = TwirpError::from_json_bytes(err.status, &err.to_resp_raw().output).unwrap();
+        // Codes that aren't in the spec are read as `internal` with the code kept in the meta
+        let read_code = parsed.meta_str("invalid_code").unwrap_or(&parsed.error_type).to_string();
+        prop_assert_eq!((read_code, parsed.msg), (code, msg));
+    }
+
+    #[test]
+    fn client_call_never_panics(root_url in uri_like(), path in uri_like(), headers in header_map()) {
+        let client = HyperClient::new(Client::new(), &root_url);
+        let mut req = ServiceRequest::new(());
+        req.headers = headers;
+        // Only building the request is of interest, the future is never run
+        drop(client.go::<_, ()>(&path, req));
+    }
+}
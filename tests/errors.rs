@@ -0,0 +1,166 @@
+#![allow(clippy::result_large_err)]
+
+mod common;
+
+use std::error::Error;
+use std::io;
+
+use common::serve;
+use futures_util::future;
+use hyper::{Body, Client, Method, Request, StatusCode, Uri};
+use prost_twirp::{DefaultErrorHandler, ErrorContext, ErrorHandler, HyperServer, HyperService, PTRes, ProstTwirpError,
+    ServiceRequest, ServiceResponse, TwirpError};
+use serde_json::{json, Value};
+
+struct Failing;
+impl HyperService for Failing {
+    fn handle(&self, req: ServiceRequest<Vec<u8>>) -> PTRes<Vec<u8>> {
+        match (req.method.clone(), req.uri.path()) {
+            (Method::POST, "/twirp/test.Failing/Decode") =>
+                Box::pin(future::ready(req.to_proto::<String>().and_then(|_| ServiceResponse::new(()).to_proto_raw()))),
+            (Method::POST, "/twirp/test.Failing/Io") => Box::pin(future::ready(Err(TwirpError::internal(
+                io::Error::other("disk on fire")).into()))),
+            (Method::POST, "/twirp/test.Failing/Uri") =>
+                Box::pin(future::ready(Err(ProstTwirpError::UriError("::".parse::<Uri>().unwrap_err())))),
+            _ => Box::pin(future::ready(Err(TwirpError::new(StatusCode::NOT_FOUND, "bad_route", "no such method").
+                into())))
+        }
+    }
+}
+
+async fn raw_call(root_url: &str, method: Method, path: &str, content_type: &str, body: Vec<u8>) -> (StatusCode, Value) {
+    let req = Request::builder().method(method).uri(format!("{}{}", root_url, path)).
+        header("content-type", content_type).body(Body::from(body)).unwrap();
+    let resp = Client::new().request(req).await.unwrap();
+    let status = resp.status();
+    let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn default_handler_is_spec_compliant() {
+    let client = serve(|_| HyperServer::new(Failing));
+
+    let (status, body) = raw_call(&client.root_url, Method::GET, "/twirp/test.Failing/Decode", "application/protobuf",
+        vec![]).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body, json!({ "code": "bad_route", "msg": "unsupported method GET (only POST is allowed)" }));
+
+    let (status, body) = raw_call(&client.root_url, Method::POST, "/twirp/test.Failing/Decode", "text/plain",
+        vec![]).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body, json!({ "code": "bad_route", "msg": "unexpected Content-Type: \"text/plain\"" }));
+
+    let (status, body) = raw_call(&client.root_url, Method::POST, "/twirp/test.Failing/Decode", "application/protobuf",
+        vec![0xff]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body, json!({ "code": "malformed", "msg": "the request could not be decoded" }));
+
+    let err = client.go::<_, ()>("/twirp/test.Failing/Uri", ServiceRequest::new(())).await.unwrap_err();
+    let err = err.twirp_error().unwrap();
+    assert_eq!((err.status, err.error_type.as_str(), err.msg.as_str()),
+        (StatusCode::INTERNAL_SERVER_ERROR, "internal", "Internal Error"));
+
+    let err = client.go::<_, ()>("/twirp/test.Failing/Missing", ServiceRequest::new(())).await.unwrap_err();
+    let err = err.twirp_error().unwrap();
+    assert_eq!((err.status, err.error_type.as_str(), err.msg.as_str()),
+        (StatusCode::NOT_FOUND, "bad_route", "no such method"));
+}
+
+#[tokio::test]
+async fn custom_handler_gets_error_and_request() {
+    let handler = |ctx: &ErrorContext, err: ProstTwirpError| {
+        let detail = match err.root_err() {
+            ProstTwirpError::UriError(err) => err.to_string(),
+            err => return DefaultErrorHandler.handle_error(ctx, err),
+        };
+        let request_id = ctx.headers.get("x-request-id").unwrap().to_str().unwrap();
+        Ok(TwirpError::new_meta(StatusCode::INTERNAL_SERVER_ERROR, "internal", "Internal Error",
+            Some(json!({ "method": ctx.rpc.method, "request_id": request_id, "detail": detail }))))
+    };
+    let server = HyperServer::new(Failing).with_error_handler(handler);
+    let client = serve(move |_| server.clone());
+
+    let mut req = ServiceRequest::new(());
+    req.headers.insert("x-request-id", "abc".parse().unwrap());
+    let err = client.go::<_, ()>("/twirp/test.Failing/Uri", req).await.unwrap_err();
+    let err = err.twirp_error().unwrap();
+    assert_eq!(err.error_type, "internal");
+    assert_eq!(err.meta, Some(json!({ "method": "Uri", "request_id": "abc", "detail": "invalid authority" })));
+
+    let err = client.go::<_, ()>("/twirp/test.Failing/Missing", ServiceRequest::new(())).await.unwrap_err();
+    assert_eq!(err.twirp_error().unwrap().error_type, "bad_route");
+}
+
+#[test]
+fn reads_legacy_error_type() {
+    let err = TwirpError::from_json(StatusCode::NOT_FOUND, json!({ "error_type": "not_found", "msg": "Not found" }));
+    assert_eq!(err.error_type, "not_found");
+    assert_eq!(err.meta, None);
+}
+
+#[tokio::test]
+async fn dev_mode_adds_cause_chain() {
+    let client = serve(|_| HyperServer::new(Failing).with_dev_mode(true));
+
+    let err = client.go::<_, ()>("/twirp/test.Failing/Uri", ServiceRequest::new(())).await.unwrap_err();
+    assert_eq!(err.twirp_error().unwrap().meta, Some(json!({ "cause": "UriError: invalid URI: invalid authority" })));
+
+    let err = client.go::<_, ()>("/twirp/test.Failing/Io", ServiceRequest::new(())).await.unwrap_err();
+    assert_eq!(err.twirp_error().unwrap().meta,
+        Some(json!({ "cause": "TwirpError: internal: Internal Error: disk on fire" })));
+
+    let (_, body) = raw_call(&client.root_url, Method::POST, "/twirp/test.Failing/Decode", "application/protobuf",
+        vec![0xff]).await;
+    assert!(body["meta"]["cause"].as_str().unwrap().
+        starts_with("AfterBodyError > ProstDecodeError: failed to decode protobuf: "), "{}", body);
+
+    // Twirp errors without a cause stay untouched
+    let err = client.go::<_, ()>("/twirp/test.Failing/Missing", ServiceRequest::new(())).await.unwrap_err();
+    assert_eq!(err.twirp_error().unwrap().meta, None);
+}
+
+#[tokio::test]
+async fn causes_stay_on_the_server() {
+    let client = serve(|_| HyperServer::new(Failing));
+    let err = client.go::<_, ()>("/twirp/test.Failing/Io", ServiceRequest::new(())).await.unwrap_err();
+    let err = err.twirp_error().unwrap();
+    assert_eq!((err.error_type.as_str(), err.meta.as_ref(), err.source().is_none()), ("internal", None, true));
+
+    let err = TwirpError::internal(io::Error::other("disk on fire"));
+    assert_eq!(err.source().unwrap().to_string(), "disk on fire");
+    assert_eq!(err.to_json(), json!({ "code": "internal", "msg": "Internal Error" }));
+}
+
+#[test]
+fn intermediary_error_body_is_cut() {
+    let mut resp = ServiceResponse::new(vec![b'x'; 2000]);
+    resp.status = StatusCode::SERVICE_UNAVAILABLE;
+    let err = TwirpError::from_response(&resp);
+    assert_eq!(err.error_type, "unavailable");
+    assert_eq!(err.meta_str("body").unwrap(), format!("{}...", "x".repeat(1024)));
+    assert!(err.cause.is_some());
+}
+
+#[test]
+fn invalid_codes_become_internal() {
+    let json = br#"{"code": "too_small", "msg": "Size too small", "meta": {"min": "1"}}"#;
+    let err = TwirpError::from_json_bytes(StatusCode::BAD_REQUEST, json).unwrap();
+    assert_eq!((err.status, err.error_type.as_str(), err.msg.as_str()),
+        (StatusCode::BAD_REQUEST, "internal", "Size too small"));
+    assert_eq!(err.meta, Some(json!({ "min": "1", "invalid_code": "too_small" })));
+
+    let mut resp = ServiceResponse::new(br#"{"error_type": "", "msg": "empty"}"#.to_vec());
+    resp.status = StatusCode::BAD_REQUEST;
+    let err = TwirpError::from_response(&resp);
+    assert_eq!((err.error_type.as_str(), err.meta_str("invalid_code")), ("internal", Some("")));
+
+    // Only Connect spells it `data_loss`
+    let json = br#"{"code": "data_loss", "msg": "gone"}"#;
+    let err = TwirpError::from_json_bytes(StatusCode::INTERNAL_SERVER_ERROR, json).unwrap();
+    assert_eq!((err.error_type.as_str(), err.meta_str("invalid_code")), ("internal", Some("data_loss")));
+
+    let json = br#"{"code": "out_of_range", "msg": "Size too small"}"#;
+    let err = TwirpError::from_json_bytes(StatusCode::BAD_REQUEST, json).unwrap();
+    assert_eq!((err.error_type.as_str(), err.meta), ("out_of_range", None));
+}
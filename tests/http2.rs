@@ -0,0 +1,79 @@
+#![cfg(feature = "http2")]
+#![allow(clippy::result_large_err)]
+
+mod common;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use common::serve_with;
+use futures_util::future;
+use hyper::{Client, StatusCode};
+use prost_twirp::{Http2, HyperClient, HyperServer, HyperService, PTRes, ServiceRequest, ServiceResponse, TwirpError};
+use tokio::sync::Barrier;
+
+/// Answers with the HTTP version of the request once `barrier` calls are in flight together
+struct Version {
+    barrier: Arc<Barrier>,
+}
+
+impl HyperService for Version {
+    fn handle(&self, req: ServiceRequest<Vec<u8>>) -> PTRes<Vec<u8>> {
+        if req.uri.path() != "/twirp/test.Echo/Version" {
+            return Box::pin(future::err(TwirpError::new(StatusCode::NOT_FOUND, "bad_route", "not found").into()));
+        }
+        let version = format!("{:?}", req.version);
+        let barrier = self.barrier.clone();
+        Box::pin(async move {
+            barrier.wait().await;
+            ServiceResponse::new(version).to_proto_raw()
+        })
+    }
+}
+
+/// Serve `Version` and return the root URL and the number of accepted connections
+fn serve(barrier: usize, http2_only: bool) -> (String, Arc<AtomicUsize>) {
+    let server = HyperServer::new(Version { barrier: Arc::new(Barrier::new(barrier)) });
+    let connections = Arc::new(AtomicUsize::new(0));
+    let accepted = connections.clone();
+    let root_url = serve_with(|builder| builder.http2_only(http2_only), move |_| {
+        accepted.fetch_add(1, Ordering::SeqCst);
+        server.clone()
+    }).root_url;
+    (root_url, connections)
+}
+
+async fn version(client: &HyperClient) -> String {
+    let req = ServiceRequest::new(String::new());
+    let resp = tokio::time::timeout(Duration::from_secs(5), client.go::<_, String>("/twirp/test.Echo/Version", req)).
+        await.expect("the call timed out").unwrap();
+    resp.output
+}
+
+#[tokio::test]
+async fn prior_knowledge_multiplexes_calls() {
+    const CALLS: usize = 20;
+    let (root_url, connections) = serve(CALLS, true);
+    let client = HyperClient::new(Http2::PriorKnowledge.client_builder().build_http(), &root_url);
+    // Every call waits for all of the others, so they can only finish when all of them are in flight at once
+    let versions = future::join_all((0..CALLS).map(|_| version(&client))).await;
+    assert!(versions.iter().all(|v| v == "HTTP/2.0"), "{:?}", versions);
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn server_answers_http1_and_http2() {
+    let (root_url, _) = serve(1, false);
+    let client = HyperClient::new(Http2::PriorKnowledge.client_builder().build_http(), &root_url);
+    assert_eq!(version(&client).await, "HTTP/2.0");
+    let client = HyperClient::new(Client::new(), &root_url);
+    assert_eq!(version(&client).await, "HTTP/1.1");
+}
+
+#[tokio::test]
+async fn alpn_falls_back_to_http1_without_tls() {
+    let (root_url, _) = serve(1, false);
+    let client = HyperClient::new(Http2::Alpn.client_builder().build_http(), &root_url);
+    assert_eq!(version(&client).await, "HTTP/1.1");
+}
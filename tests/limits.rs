@@ -0,0 +1,145 @@
+#![allow(clippy::result_large_err)]
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::{serve, Echo};
+use hyper::Client;
+use prost_twirp::{ConcurrencyLimits, HyperClient, HyperServer, ProstTwirpError, ServiceRequest, TwirpMetrics};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+/// Serve `Echo` with the given limits, returning the root URL, the metrics and the notification of entered sleeps
+fn serve_limited(limits: ConcurrencyLimits) -> (String, Arc<TwirpMetrics>, Arc<Notify>) {
+    let echo = Echo::default();
+    let entered = echo.entered.clone();
+    let metrics = Arc::new(TwirpMetrics::new());
+    let server = HyperServer::new(echo).with_hooks(metrics.clone()).with_concurrency_limit(limits);
+    (serve(move |_| server.clone()).root_url, metrics, entered)
+}
+
+async fn call(root_url: &str, path: &str, input: &str) -> Result<String, ProstTwirpError> {
+    let client = HyperClient::new(Client::new(), root_url);
+    client.go::<_, String>(path, ServiceRequest::new(input.to_string())).await.map(|v| v.output)
+}
+
+fn spawn_call(root_url: &str, path: &'static str, input: &'static str) -> JoinHandle<Result<String, ProstTwirpError>> {
+    let root_url = root_url.to_string();
+    tokio::spawn(async move { call(&root_url, path, input).await })
+}
+
+fn error_type(err: ProstTwirpError) -> String {
+    match err.root_err() {
+        ProstTwirpError::TwirpError(err) => err.error_type.clone(),
+        err => panic!("expected a Twirp error, got {:?}", err),
+    }
+}
+
+fn limited_line(method: &str, limit: &str, shed: bool) -> String {
+    format!("twirp_server_requests_limited_total{{twirp_service=\"test.Echo\",twirp_method=\"{}\",twirp_limit=\"{}\",\
+        twirp_shed=\"{}\"}} 1", method, limit, shed)
+}
+
+#[tokio::test]
+async fn sheds_requests_beyond_the_global_limit() {
+    let (root_url, metrics, entered) = serve_limited(ConcurrencyLimits::new().with_max_in_flight(1));
+    // Requests are only counted under their method once it answered one
+    assert_eq!(call(&root_url, "/twirp/test.Echo/Echo", "hello").await.unwrap(), "hello");
+    let slow = spawn_call(&root_url, "/twirp/test.Echo/Sleep", "300");
+    entered.notified().await;
+
+    let err = call(&root_url, "/twirp/test.Echo/Echo", "hello").await.unwrap_err();
+    assert_eq!(error_type(err), "resource_exhausted");
+    let text = metrics.render();
+    assert!(text.contains(&limited_line("Echo", "global", true)), "{}", text);
+    assert!(text.contains("twirp_server_responses_total{twirp_service=\"test.Echo\",twirp_method=\"Echo\",\
+        twirp_code=\"resource_exhausted\"} 1"), "{}", text);
+
+    // Room is given back once the request in flight is done
+    assert_eq!(slow.await.unwrap().unwrap(), "300");
+    assert_eq!(call(&root_url, "/twirp/test.Echo/Echo", "hello").await.unwrap(), "hello");
+}
+
+#[tokio::test]
+async fn shed_requests_to_unknown_methods_are_counted_as_unknown() {
+    let (root_url, metrics, entered) = serve_limited(ConcurrencyLimits::new().with_max_in_flight(1));
+    let slow = spawn_call(&root_url, "/twirp/test.Echo/Sleep", "300");
+    entered.notified().await;
+
+    for i in 0..5 {
+        let err = call(&root_url, &format!("/twirp/test.Made{}/Up{}", i, i), "hello").await.unwrap_err();
+        assert_eq!(error_type(err), "resource_exhausted");
+    }
+    let text = metrics.render();
+    assert!(!text.contains("Made"), "{}", text);
+    assert!(text.contains("twirp_server_requests_limited_total{twirp_service=\"unknown\",twirp_method=\"unknown\",\
+        twirp_limit=\"global\",twirp_shed=\"true\"} 5"), "{}", text);
+    assert!(text.contains("twirp_server_responses_total{twirp_service=\"unknown\",twirp_method=\"unknown\",\
+        twirp_code=\"resource_exhausted\"} 5"), "{}", text);
+    slow.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn method_limits_only_apply_to_their_method() {
+    let limits = ConcurrencyLimits::new().with_method_max_in_flight("/twirp/test.Echo/Sleep", 1);
+    let (root_url, metrics, entered) = serve_limited(limits);
+    // Requests are only counted under their method once it answered one
+    assert_eq!(call(&root_url, "/twirp/test.Echo/Sleep", "0").await.unwrap(), "0");
+    entered.notified().await;
+    let slow = spawn_call(&root_url, "/twirp/test.Echo/Sleep", "300");
+    entered.notified().await;
+
+    assert_eq!(call(&root_url, "/twirp/test.Echo/Echo", "hello").await.unwrap(), "hello");
+    let err = call(&root_url, "/twirp/test.Echo/Sleep", "0").await.unwrap_err();
+    assert_eq!(error_type(err), "resource_exhausted");
+    let text = metrics.render();
+    assert!(text.contains(&limited_line("Sleep", "/twirp/test.Echo/Sleep", true)), "{}", text);
+    slow.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn reports_every_full_limit() {
+    let limits = ConcurrencyLimits::new().with_max_in_flight(1).with_method_max_in_flight("/twirp/test.Echo/Sleep", 1).
+        with_queue_timeout(Duration::from_secs(5));
+    let (root_url, metrics, entered) = serve_limited(limits);
+    let call = |path, input| spawn_call(&root_url, path, input);
+
+    // The second sleep waits for its method, and then for the echo that took the global room in the meantime
+    let first = call("/twirp/test.Echo/Sleep", "200");
+    entered.notified().await;
+    let echo = call("/twirp/test.Echo/Echo", "hello");
+    let second = call("/twirp/test.Echo/Sleep", "0");
+    for resp in [first, echo, second] {
+        resp.await.unwrap().unwrap();
+    }
+    let text = metrics.render();
+    assert!(text.contains(&limited_line("Echo", "global", false)), "{}", text);
+    assert!(text.contains(&limited_line("Sleep", "/twirp/test.Echo/Sleep", false)), "{}", text);
+    assert!(text.contains(&limited_line("Sleep", "global", false)), "{}", text);
+}
+
+#[tokio::test]
+async fn queues_requests_for_a_bounded_time() {
+    let limits = ConcurrencyLimits::new().with_max_in_flight(1).with_queue_timeout(Duration::from_millis(500));
+    let (root_url, metrics, entered) = serve_limited(limits);
+    let sleep = |input| spawn_call(&root_url, "/twirp/test.Echo/Sleep", input);
+
+    // Waits for the short request in flight
+    let short = sleep("100");
+    entered.notified().await;
+    assert_eq!(call(&root_url, "/twirp/test.Echo/Echo", "hello").await.unwrap(), "hello");
+    short.await.unwrap().unwrap();
+    let text = metrics.render();
+    assert!(text.contains(&limited_line("Echo", "global", false)), "{}", text);
+
+    // Gives up on a long one
+    let long = sleep("1000");
+    entered.notified().await;
+    let err = call(&root_url, "/twirp/test.Echo/Echo", "hello").await.unwrap_err();
+    assert_eq!(error_type(err), "unavailable");
+    let text = metrics.render();
+    assert!(text.contains(&limited_line("Echo", "global", true)), "{}", text);
+    long.await.unwrap().unwrap();
+}
@@ -0,0 +1,66 @@
+use std::collections::BTreeMap;
+
+use hyper::StatusCode;
+use prost_twirp::TwirpError;
+use serde_derive::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+enum Unit { Inches, Centimeters }
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct MinMaxSize {
+    min: i32,
+    max: i32,
+    unit: Unit,
+    note: String,
+    hint: Option<String>,
+    allowed: Vec<u8>,
+}
+
+fn size() -> MinMaxSize {
+    MinMaxSize {
+        min: 1,
+        max: 10,
+        unit: Unit::Inches,
+        note: "42".to_string(),
+        hint: None,
+        allowed: vec![1, 2],
+    }
+}
+
+fn map(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+    pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+#[test]
+fn typed_meta_is_a_string_map() {
+    let err = TwirpError::new(StatusCode::BAD_REQUEST, "out_of_range", "Size out of range").
+        with_meta_str("argument", "inches").
+        with_meta(&size()).unwrap();
+    assert_eq!(err.meta_map(), map(&[
+        ("allowed", "[1,2]"), ("argument", "inches"), ("max", "10"), ("min", "1"), ("note", "42"), ("unit", "Inches"),
+    ]));
+    assert_eq!(err.meta_str("argument"), Some("inches"));
+    assert_eq!(err.meta_str("hint"), None);
+
+    // Round trip through the wire format
+    let err = TwirpError::from_json_bytes(err.status, &err.to_json_bytes().unwrap()).unwrap();
+    assert_eq!(err.meta_as::<MinMaxSize>().unwrap(), size());
+    assert_eq!(err.meta_as::<BTreeMap<String, String>>().unwrap(), err.meta_map());
+}
+
+#[test]
+fn untyped_meta_is_encoded_per_spec() {
+    let err = TwirpError::new_meta(StatusCode::BAD_REQUEST, "invalid_argument", "Bad",
+        Some(json!({ "min": 1, "name": "x", "gone": null, "nested": { "a": true } })));
+    assert_eq!(err.to_json()["meta"], json!({ "min": "1", "name": "x", "nested": "{\"a\":true}" }));
+
+    let err = TwirpError::new_meta(StatusCode::BAD_REQUEST, "invalid_argument", "Bad", Some(json!([1, 2])));
+    assert_eq!(err.meta_map(), map(&[("value", "[1,2]")]));
+
+    let err = TwirpError::new(StatusCode::BAD_REQUEST, "invalid_argument", "Bad");
+    assert!(err.to_json().get("meta").is_none());
+    assert!(err.meta_as::<MinMaxSize>().is_err());
+    assert!(err.with_meta(&"not a map").is_err());
+}
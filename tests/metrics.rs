@@ -0,0 +1,84 @@
+#![allow(clippy::result_large_err)]
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{serve, Upper};
+use hyper::{Body, Request, StatusCode};
+use hyper::service::Service;
+use prost_twirp::{HyperServer, ServiceRequest, ServiceResponse, TwirpMetrics, TwirpRouter, PROMETHEUS_CONTENT_TYPE};
+
+#[tokio::test]
+async fn counts_client_and_server_rpcs() {
+    let server_metrics = Arc::new(TwirpMetrics::with_buckets(&[60.0]));
+    let client_metrics = Arc::new(TwirpMetrics::with_buckets(&[60.0]));
+
+    let hooks = server_metrics.clone();
+    let client = serve(move |_| HyperServer::new(Upper).with_hooks(hooks.clone())).with_hooks(client_metrics.clone());
+
+    for _ in 0..2 {
+        let res: ServiceResponse<String> =
+            client.go("/twirp/test.Strings/Upper", ServiceRequest::new("hello".to_string())).await.unwrap();
+        assert_eq!(res.output, "HELLO");
+    }
+    client.go::<_, String>("/twirp/test.Strings/Lower", ServiceRequest::new("hello".to_string())).await.unwrap_err();
+
+    for (metrics, side) in &[(&server_metrics, "server"), (&client_metrics, "client")] {
+        let text = metrics.render();
+        let upper = "twirp_service=\"test.Strings\",twirp_method=\"Upper\"";
+        let lower = "twirp_service=\"test.Strings\",twirp_method=\"Lower\"";
+        for line in &[
+            format!("# TYPE twirp_{}_requests_started_total counter", side),
+            format!("twirp_{}_requests_started_total{{{}}} 2", side, upper),
+            format!("twirp_{}_requests_in_flight{{{}}} 0", side, upper),
+            format!("twirp_{}_responses_total{{{},twirp_code=\"ok\"}} 2", side, upper),
+            format!("twirp_{}_responses_total{{{},twirp_code=\"not_found\"}} 1", side, lower),
+            format!("# TYPE twirp_{}_request_duration_seconds histogram", side),
+            format!("twirp_{}_request_duration_seconds_bucket{{{},le=\"60\"}} 2", side, upper),
+            format!("twirp_{}_request_duration_seconds_bucket{{{},le=\"+Inf\"}} 2", side, upper),
+            format!("twirp_{}_request_duration_seconds_count{{{}}} 2", side, upper),
+        ] {
+            assert!(text.lines().any(|l| l == line), "missing `{}` in:\n{}", line, text);
+        }
+        let other = if *side == "server" { "client" } else { "server" };
+        assert!(!text.contains(&format!("twirp_{}_", other)));
+    }
+}
+
+#[tokio::test]
+async fn handler_renders_text_exposition() {
+    let metrics = Arc::new(TwirpMetrics::new());
+    let resp = metrics.handler().call(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()[hyper::header::CONTENT_TYPE], PROMETHEUS_CONTENT_TYPE);
+    let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+    assert_eq!(body, metrics.render().as_bytes());
+}
+
+#[tokio::test]
+async fn bounds_server_series_of_unknown_methods() {
+    let metrics = Arc::new(TwirpMetrics::with_buckets(&[60.0]));
+    let hooks = metrics.clone();
+    let client = serve(move |_| HyperServer::new(TwirpRouter::new().with_service(Upper)).with_hooks(hooks.clone()));
+
+    // The first request to a method is in flight as unknown, so both series exist from then on
+    client.go::<_, String>("/twirp/test.Strings/Upper", ServiceRequest::new("hello".to_string())).await.unwrap();
+    let series = |text: &str| text.lines().filter(|l| l.starts_with("twirp_server_requests_started_total")).count();
+    let before = series(&metrics.render());
+    assert_eq!(before, 2);
+    for i in 0..50 {
+        let path = format!("/twirp/bogus{}/Method{}", i, i);
+        client.go::<_, String>(&path, ServiceRequest::new("hello".to_string())).await.unwrap_err();
+    }
+    let text = metrics.render();
+    assert_eq!(series(&text), before, "{}", text);
+    for line in &[
+        "twirp_server_requests_started_total{twirp_service=\"unknown\",twirp_method=\"unknown\"} 50",
+        "twirp_server_requests_in_flight{twirp_service=\"unknown\",twirp_method=\"unknown\"} 0",
+        "twirp_server_responses_total{twirp_service=\"unknown\",twirp_method=\"unknown\",twirp_code=\"bad_route\"} 50",
+        "twirp_server_requests_started_total{twirp_service=\"test.Strings\",twirp_method=\"Upper\"} 1",
+    ] {
+        assert!(text.lines().any(|l| l == *line), "missing `{}` in:\n{}", line, text);
+    }
+}
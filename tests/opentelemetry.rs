@@ -0,0 +1,57 @@
+#![cfg(feature = "opentelemetry")]
+#![allow(clippy::result_large_err)]
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use common::serve;
+use futures_util::future;
+use opentelemetry::global;
+use opentelemetry::trace::{SpanId, TracerProvider};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider};
+use prost_twirp::{HyperServer, HyperService, PTRes, ServiceRequest, ServiceResponse};
+use tracing_subscriber::layer::SubscriberExt;
+
+/// Echoes the input and remembers the `traceparent` header it was called with
+#[derive(Clone, Default)]
+struct Echo(Arc<Mutex<Option<String>>>);
+
+impl HyperService for Echo {
+    fn handle(&self, req: ServiceRequest<Vec<u8>>) -> PTRes<Vec<u8>> {
+        *self.0.lock().unwrap() = req.headers.get("traceparent").map(|v| v.to_str().unwrap().to_string());
+        Box::pin(future::ok(ServiceResponse::new(req.input)))
+    }
+}
+
+#[tokio::test]
+async fn propagates_trace_context() {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    let exporter = InMemorySpanExporter::default();
+    let provider = SdkTracerProvider::builder().with_simple_exporter(exporter.clone()).build();
+    let subscriber = tracing_subscriber::registry().
+        with(tracing_opentelemetry::layer().with_tracer(provider.tracer("prost-twirp-test")));
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let echo = Echo::default();
+    let server = HyperServer::new(echo.clone());
+    let client = serve(move |_| server.clone());
+
+    let res: ServiceResponse<String> =
+        client.go("/twirp/test.Echo/Echo", ServiceRequest::new("hi".to_string())).await.unwrap();
+    assert_eq!(res.output, "hi");
+    provider.force_flush().unwrap();
+
+    let spans = exporter.get_finished_spans().unwrap();
+    let client_span = spans.iter().find(|s| s.name == "twirp.client").expect("client span");
+    let server_span = spans.iter().find(|s| s.name == "twirp.server").expect("server span");
+    assert_ne!(client_span.span_context.span_id(), SpanId::INVALID);
+    assert_eq!(server_span.span_context.trace_id(), client_span.span_context.trace_id());
+    assert_eq!(server_span.parent_span_id, client_span.span_context.span_id());
+    assert!(server_span.parent_span_is_remote);
+
+    let traceparent = echo.0.lock().unwrap().clone().expect("traceparent header");
+    assert_eq!(traceparent, format!("00-{}-{}-01",
+        client_span.span_context.trace_id(), client_span.span_context.span_id()));
+}
@@ -0,0 +1,56 @@
+#![allow(clippy::result_large_err)]
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use common::{serve, Upper};
+use futures_util::future;
+use hyper::{Method, StatusCode};
+use prost_twirp::{panic_message, HyperServer, HyperService, PTRes, ServiceRequest, ServiceResponse, TwirpError,
+    TwirpRouter};
+
+struct Panicky;
+impl HyperService for Panicky {
+    fn handle(&self, req: ServiceRequest<Vec<u8>>) -> PTRes<Vec<u8>> {
+        match (req.method.clone(), req.uri.path()) {
+            (Method::POST, "/twirp/test.Panicky/Now") => panic!("panicked now"),
+            (Method::POST, "/twirp/test.Panicky/Later") => Box::pin(async move {
+                if !req.input.is_empty() { panic!("panicked later with {} bytes", req.input.len()); }
+                ServiceResponse::new(String::new()).to_proto_raw()
+            }),
+            _ => Box::pin(future::ok(TwirpError::new(StatusCode::NOT_FOUND, "not_found", "Not found").to_resp_raw()))
+        }
+    }
+}
+
+#[tokio::test]
+async fn converts_panics_into_internal_errors() {
+    let panics = Arc::new(Mutex::new(Vec::new()));
+    let reported = panics.clone();
+    let client = serve(move |_| {
+        let reported = reported.clone();
+        HyperServer::new(TwirpRouter::new().with_service(Upper).with_service(Panicky)).
+            with_panic_hook(move |rpc, payload| {
+                reported.lock().unwrap().push((rpc.method.clone(), panic_message(payload).unwrap().to_string()));
+            })
+    });
+
+    for method in &["Now", "Later"] {
+        let err = client.go::<_, String>(&format!("/twirp/test.Panicky/{}", method),
+            ServiceRequest::new("hello".to_string())).await.unwrap_err();
+        let err = err.twirp_error().unwrap();
+        assert_eq!(err.status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(err.error_type, "internal");
+        assert_eq!(err.msg, "Internal Error");
+    }
+    // The server keeps serving after a panic
+    let res: ServiceResponse<String> =
+        client.go("/twirp/test.Strings/Upper", ServiceRequest::new("hello".to_string())).await.unwrap();
+    assert_eq!(res.output, "HELLO");
+
+    assert_eq!(*panics.lock().unwrap(), vec![
+        ("Now".to_string(), "panicked now".to_string()),
+        ("Later".to_string(), "panicked later with 7 bytes".to_string()),
+    ]);
+}
@@ -0,0 +1,155 @@
+#![allow(clippy::result_large_err)]
+
+mod common;
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use common::{Echo, Upper, ECHO, STRINGS};
+use futures_util::future;
+use hyper::{Client, StatusCode};
+use prost_twirp::{ErrorContext, HyperClient, HyperService, PTRes, ProstTwirpError, ServiceRequest, ServiceResponse,
+    TwirpError, TwirpRouter, TwirpServerBuilder};
+
+/// Answers every request with its path
+struct Fallback;
+impl HyperService for Fallback {
+    fn handle(&self, req: ServiceRequest<Vec<u8>>) -> PTRes<Vec<u8>> {
+        Box::pin(future::ready(ServiceResponse::new(req.uri.path().to_string()).to_proto_raw()))
+    }
+}
+
+fn any_port() -> SocketAddr { SocketAddr::from(([127, 0, 0, 1], 0)) }
+
+async fn call(addr: SocketAddr, path: &str, input: &str) -> Result<String, ProstTwirpError> {
+    let client = HyperClient::new(Client::new(), &format!("http://{}", addr));
+    client.go::<_, String>(path, ServiceRequest::new(input.to_string())).await.map(|v| v.output)
+}
+
+fn error_type(err: ProstTwirpError) -> String {
+    match err.root_err() {
+        ProstTwirpError::TwirpError(err) => err.error_type.clone(),
+        err => panic!("expected a Twirp error, got {:?}", err),
+    }
+}
+
+#[test]
+fn router_routes_by_descriptor() {
+    let router = TwirpRouter::new().with_service(Echo::default()).with_service(Upper);
+    assert_eq!(router.descriptors(), vec![&ECHO, &STRINGS]);
+    assert_eq!(router.descriptor(), None);
+    assert_eq!(TwirpRouter::new().with_service(Upper).descriptor(), Some(&STRINGS));
+}
+
+#[tokio::test]
+async fn serves_several_services_on_several_addresses() {
+    let handle = TwirpServerBuilder::new().with_service(Echo::default()).with_service(Upper).
+        bind(any_port()).bind(any_port()).start().await.unwrap();
+    assert_eq!(handle.local_addrs().len(), 2);
+    for addr in handle.local_addrs() {
+        assert_eq!(call(*addr, "/twirp/test.Echo/Echo", "hello").await.unwrap(), "hello");
+        assert_eq!(call(*addr, "/twirp/test.Strings/Upper", "hello").await.unwrap(), "HELLO");
+        let err = call(*addr, "/twirp/test.EchoNot/Echo", "hello").await.unwrap_err();
+        assert_eq!(error_type(err), "bad_route");
+    }
+    assert!(handle.shutdown(Duration::from_secs(1)).await.is_empty());
+}
+
+#[tokio::test]
+async fn unmatched_requests_go_to_service_without_descriptor() {
+    let handle = TwirpServerBuilder::new().with_service(Upper).with_service(Fallback).bind(any_port()).start().await.
+        unwrap();
+    let addr = handle.local_addrs()[0];
+    assert_eq!(call(addr, "/twirp/test.Strings/Upper", "hello").await.unwrap(), "HELLO");
+    assert_eq!(call(addr, "/twirp/test.Other/Method", "hello").await.unwrap(), "/twirp/test.Other/Method");
+}
+
+#[tokio::test]
+async fn shutdown_drains_requests_in_flight() {
+    let echo = Echo::default();
+    let entered = echo.entered.clone();
+    let handle = TwirpServerBuilder::new().with_service(echo).bind(any_port()).start().await.unwrap();
+    let addr = handle.local_addrs()[0];
+
+    let slow = tokio::spawn(call(addr, "/twirp/test.Echo/Sleep", "200"));
+    entered.notified().await;
+    let aborted = handle.shutdown(Duration::from_secs(5)).await;
+    assert!(aborted.is_empty(), "{:?}", aborted);
+    assert_eq!(slow.await.unwrap().unwrap(), "200");
+
+    // New connections aren't accepted anymore
+    assert!(matches!(call(addr, "/twirp/test.Echo/Echo", "hello").await, Err(ProstTwirpError::HyperError(_))));
+}
+
+#[tokio::test]
+async fn shutdown_reports_aborted_requests() {
+    let echo = Echo::default();
+    let entered = echo.entered.clone();
+    let handle = TwirpServerBuilder::new().with_service(echo).bind(any_port()).start().await.unwrap();
+    let addr = handle.local_addrs()[0];
+
+    let slow = tokio::spawn(call(addr, "/twirp/test.Echo/Sleep", "10000"));
+    entered.notified().await;
+    let aborted = handle.shutdown(Duration::from_millis(100)).await;
+    assert_eq!(aborted.len(), 1);
+    assert_eq!(aborted[0].path, "/twirp/test.Echo/Sleep");
+    assert!(matches!(slow.await.unwrap(), Err(ProstTwirpError::HyperError(_))));
+}
+
+#[tokio::test]
+async fn shutdown_forgets_requests_of_disconnected_clients() {
+    let echo = Echo::default();
+    let entered = echo.entered.clone();
+    let handle = TwirpServerBuilder::new().with_service(echo).bind(any_port()).start().await.unwrap();
+    let addr = handle.local_addrs()[0];
+
+    let slow = tokio::spawn(call(addr, "/twirp/test.Echo/Sleep", "10000"));
+    entered.notified().await;
+    slow.abort();
+    let _ = slow.await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let aborted = handle.shutdown(Duration::from_secs(5)).await;
+    assert!(aborted.is_empty(), "{:?}", aborted);
+}
+
+#[tokio::test]
+async fn limits_open_connections() {
+    let handle = TwirpServerBuilder::new().with_service(Echo::default()).with_max_connections(1).bind(any_port()).
+        start().await.unwrap();
+    let addr = handle.local_addrs()[0];
+
+    // An idle connection takes the only spot until it's closed
+    let idle = tokio::net::TcpStream::connect(addr).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let waiting = tokio::time::timeout(Duration::from_millis(200), call(addr, "/twirp/test.Echo/Echo", "hello")).await;
+    assert!(waiting.is_err());
+    drop(idle);
+    let resp = tokio::time::timeout(Duration::from_secs(5), call(addr, "/twirp/test.Echo/Echo", "hello")).await;
+    assert_eq!(resp.unwrap().unwrap(), "hello");
+}
+
+#[tokio::test]
+async fn configures_the_server() {
+    let handle = TwirpServerBuilder::new().with_service(Upper).
+        with_server(|server| server.with_error_handler(|_: &ErrorContext, _: ProstTwirpError| {
+            Ok(TwirpError::new(StatusCode::SERVICE_UNAVAILABLE, "unavailable", "configured"))
+        })).
+        bind(any_port()).start().await.unwrap();
+    let err = call(handle.local_addrs()[0], "/twirp/test.Other/Method", "").await.unwrap_err();
+    assert_eq!(error_type(err), "unavailable");
+}
+
+#[tokio::test]
+async fn failing_to_bind_serves_nothing() {
+    let taken = std::net::TcpListener::bind(any_port()).unwrap();
+    let free = std::net::TcpListener::bind(any_port()).unwrap();
+    let free_addr = free.local_addr().unwrap();
+    drop(free);
+
+    let started = TwirpServerBuilder::new().with_service(Upper).bind(free_addr).bind(taken.local_addr().unwrap()).
+        start().await;
+    assert_eq!(started.unwrap_err().kind(), std::io::ErrorKind::AddrInUse);
+    // The address bound first was let go of again
+    assert!(call(free_addr, "/twirp/test.Strings/Upper", "hello").await.is_err());
+    std::net::TcpListener::bind(free_addr).unwrap();
+}
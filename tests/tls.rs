@@ -0,0 +1,168 @@
+#![cfg(feature = "rustls")]
+#![allow(clippy::result_large_err)]
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use futures_util::future;
+use hyper::{Client, Server, StatusCode};
+use hyper::service::make_service_fn;
+use prost_twirp::{https_connector, load_certificates, load_private_key, tls_client_config, tls_server_config,
+    HttpsConnector, HyperClient, HyperServer, HyperService, PTRes, ServiceRequest, ServiceResponse, TlsConn,
+    TlsIncoming, TwirpError, TwirpServerBuilder};
+use prost_twirp::rustls::{Certificate, PrivateKey, RootCertStore};
+use rcgen::{BasicConstraints, CertificateParams, IsCa};
+
+/// Answers with the DER of the client certificate, empty without one
+struct Identity;
+impl HyperService for Identity {
+    fn handle(&self, req: ServiceRequest<Vec<u8>>) -> PTRes<Vec<u8>> {
+        if req.uri.path() != "/twirp/test.Identity/Get" {
+            return Box::pin(future::err(TwirpError::new(StatusCode::NOT_FOUND, "bad_route", "not found").into()));
+        }
+        let cert = req.peer_identity.as_ref().and_then(|v| v.certificates.first().cloned()).unwrap_or_default();
+        Box::pin(future::ready(ServiceResponse::new(cert).to_proto_raw()))
+    }
+}
+
+/// A CA along with PEM-encoded certificates and keys it signed
+struct Pki {
+    ca: rcgen::Certificate,
+}
+
+impl Pki {
+    fn new() -> Pki {
+        let mut params = CertificateParams::new(Vec::new());
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        Pki { ca: rcgen::Certificate::from_params(params).unwrap() }
+    }
+
+    fn roots(&self) -> RootCertStore {
+        let mut roots = RootCertStore::empty();
+        roots.add(&Certificate(self.ca.serialize_der().unwrap())).unwrap();
+        roots
+    }
+
+    /// A PEM certificate and PEM key for the given DNS name
+    fn issue(&self, name: &str) -> (String, String) {
+        let cert = rcgen::Certificate::from_params(CertificateParams::new(vec![name.to_string()])).unwrap();
+        (cert.serialize_pem_with_signer(&self.ca).unwrap(), cert.serialize_private_key_pem())
+    }
+}
+
+fn identity(pem: &(String, String)) -> (Vec<Certificate>, PrivateKey) {
+    (load_certificates(pem.0.as_bytes()).unwrap(), load_private_key(pem.1.as_bytes()).unwrap())
+}
+
+/// Serve `Identity` over TLS, asking for client certificates signed by `client_roots` when given
+fn serve(pki: &Pki, client_roots: Option<RootCertStore>) -> String { serve_with(pki, client_roots, |v| v) }
+
+/// Like `serve`, configuring the `TlsIncoming` with `configure`
+fn serve_with(pki: &Pki, client_roots: Option<RootCertStore>, configure: impl FnOnce(TlsIncoming) -> TlsIncoming)
+        -> String {
+    let (cert_chain, key) = identity(&pki.issue("localhost"));
+    let config = tls_server_config(cert_chain, key, client_roots).unwrap();
+    let incoming = configure(TlsIncoming::bind(&SocketAddr::from(([127, 0, 0, 1], 0)), config).unwrap());
+    let root_url = format!("https://localhost:{}", incoming.local_addr().port());
+    let server = HyperServer::new(Identity);
+    let make_service = make_service_fn(move |conn: &TlsConn| {
+        let mut server = server.clone().with_peer(conn.remote_addr());
+        if let Some(peer_identity) = conn.peer_identity() {
+            server = server.with_peer_identity(peer_identity);
+        }
+        async { Ok::<_, Infallible>(server) }
+    });
+    tokio::spawn(Server::builder(incoming).serve(make_service));
+    root_url
+}
+
+fn client(pki: &Pki, root_url: &str, identity: Option<(Vec<Certificate>, PrivateKey)>) -> HyperClient<HttpsConnector> {
+    let config = tls_client_config(pki.roots(), identity).unwrap();
+    HyperClient::new(Client::builder().build(https_connector(config)), root_url)
+}
+
+async fn peer_certificate(client: &HyperClient<HttpsConnector>) -> Result<Vec<u8>, prost_twirp::ProstTwirpError> {
+    client.go::<_, Vec<u8>>("/twirp/test.Identity/Get", ServiceRequest::new(Vec::new())).await.map(|v| v.output)
+}
+
+#[tokio::test]
+async fn serves_https() {
+    let pki = Pki::new();
+    let root_url = serve(&pki, None);
+    let cert = peer_certificate(&client(&pki, &root_url, None)).await.unwrap();
+    assert!(cert.is_empty());
+
+    // Servers outside of the trusted roots are refused
+    let client = client(&Pki::new(), &root_url, None);
+    assert!(peer_certificate(&client).await.is_err());
+}
+
+#[tokio::test]
+async fn mutual_tls_exposes_peer_identity() {
+    let pki = Pki::new();
+    let root_url = serve(&pki, Some(pki.roots()));
+    let client_identity = identity(&pki.issue("client.test"));
+    let client_cert = client_identity.0[0].0.clone();
+    let cert = peer_certificate(&client(&pki, &root_url, Some(client_identity))).await.unwrap();
+    assert_eq!(cert, client_cert);
+}
+
+#[tokio::test]
+async fn mutual_tls_rejects_unknown_clients() {
+    let pki = Pki::new();
+    let root_url = serve(&pki, Some(pki.roots()));
+    assert!(peer_certificate(&client(&pki, &root_url, None)).await.is_err());
+    let other_identity = identity(&Pki::new().issue("client.test"));
+    assert!(peer_certificate(&client(&pki, &root_url, Some(other_identity))).await.is_err());
+
+    // The server keeps serving known clients after failed handshakes
+    let client_identity = identity(&pki.issue("client.test"));
+    assert!(peer_certificate(&client(&pki, &root_url, Some(client_identity))).await.is_ok());
+}
+
+#[tokio::test]
+async fn limits_handshakes_in_flight() {
+    let pki = Pki::new();
+    let root_url = serve_with(&pki, None, |v| v.with_max_handshakes(1).with_handshake_timeout(Duration::from_secs(30)));
+    let addr = root_url.trim_start_matches("https://").replace("localhost", "127.0.0.1");
+
+    // A client that never starts its handshake takes the only spot until it's gone
+    let stalled = tokio::net::TcpStream::connect(&addr).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let client = client(&pki, &root_url, None);
+    assert!(tokio::time::timeout(Duration::from_millis(200), peer_certificate(&client)).await.is_err());
+    drop(stalled);
+    let cert = tokio::time::timeout(Duration::from_secs(5), peer_certificate(&client)).await.unwrap().unwrap();
+    assert!(cert.is_empty());
+}
+
+#[tokio::test]
+async fn server_builder_serves_mutual_tls() {
+    let pki = Pki::new();
+    let (cert_chain, key) = identity(&pki.issue("localhost"));
+    let handle = TwirpServerBuilder::new().with_service(Identity).
+        with_tls(tls_server_config(cert_chain, key, Some(pki.roots())).unwrap()).
+        bind(SocketAddr::from(([127, 0, 0, 1], 0))).start().await.unwrap();
+    let root_url = format!("https://localhost:{}", handle.local_addrs()[0].port());
+    let client_identity = identity(&pki.issue("client.test"));
+    let client_cert = client_identity.0[0].0.clone();
+    let cert = peer_certificate(&client(&pki, &root_url, Some(client_identity))).await.unwrap();
+    assert_eq!(cert, client_cert);
+    assert!(handle.shutdown(Duration::from_secs(1)).await.is_empty());
+}
+
+#[cfg(feature = "http2")]
+#[tokio::test]
+async fn negotiates_http2_with_alpn() {
+    let pki = Pki::new();
+    let root_url = serve(&pki, None);
+    let config = tls_client_config(pki.roots(), None).unwrap();
+    let connector = https_connector(config);
+    let client = prost_twirp::Http2::Alpn.client_builder().build::<_, hyper::Body>(connector);
+    let req = hyper::Request::post(format!("{}/twirp/test.Identity/Get", root_url)).
+        header("content-type", "application/protobuf").body(hyper::Body::empty()).unwrap();
+    let resp = client.request(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.version(), hyper::Version::HTTP_2);
+}
@@ -0,0 +1,91 @@
+#![cfg(feature = "tracing")]
+#![allow(clippy::result_large_err)]
+
+mod common;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use common::{serve, Upper};
+use prost_twirp::{HyperServer, ServiceRequest, ServiceResponse};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+
+type Fields = HashMap<String, String>;
+
+/// Collects the fields of every closed span
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<(String, Fields)>>>);
+
+struct FieldVisitor<'a>(&'a mut Fields);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0.insert(field.name().to_string(), format!("{:?}", value));
+    }
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Recorder {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut fields = Fields::new();
+        attrs.record(&mut FieldVisitor(&mut fields));
+        ctx.span(id).unwrap().extensions_mut().insert(fields);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let span = ctx.span(id).unwrap();
+        let mut extensions = span.extensions_mut();
+        values.record(&mut FieldVisitor(extensions.get_mut::<Fields>().unwrap()));
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let span = ctx.span(&id).unwrap();
+        let fields = span.extensions_mut().remove::<Fields>().unwrap();
+        self.0.lock().unwrap().push((span.name().to_string(), fields));
+    }
+}
+
+impl Recorder {
+    fn span(&self, name: &str, method: &str) -> Fields {
+        self.0.lock().unwrap().iter().
+            find(|(n, fields)| n == name && fields.get("rpc.method").map(String::as_str) == Some(method)).
+            map(|(_, fields)| fields.clone()).
+            unwrap_or_else(|| panic!("no {} span for {}", name, method))
+    }
+}
+
+#[tokio::test]
+async fn records_client_and_server_spans() {
+    let recorder = Recorder::default();
+    let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(recorder.clone()));
+
+    let client = serve(|_| HyperServer::new(Upper));
+
+    let res: ServiceResponse<String> =
+        client.go("/twirp/test.Strings/Upper", ServiceRequest::new("hello".to_string())).await.unwrap();
+    assert_eq!(res.output, "HELLO");
+    let err = client.go::<_, String>("/twirp/test.Strings/Lower", ServiceRequest::new("hello".to_string())).await.
+        unwrap_err();
+    assert_eq!(err.twirp_error().unwrap().error_type, "not_found");
+
+    for name in &["twirp.client", "twirp.server"] {
+        let ok = recorder.span(name, "Upper");
+        assert_eq!(ok["rpc.service"], "test.Strings");
+        assert_eq!(ok["http.status_code"], "200");
+        assert_eq!(ok["request.size"], "7");
+        assert_eq!(ok["response.size"], "7");
+        assert!(!ok.contains_key("twirp.error_code"));
+        assert!(ok.contains_key("latency_ms"));
+
+        let not_found = recorder.span(name, "Lower");
+        assert_eq!(not_found["http.status_code"], "404");
+        assert_eq!(not_found["twirp.error_code"], "not_found");
+    }
+}
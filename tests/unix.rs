@@ -0,0 +1,70 @@
+#![cfg(unix)]
+#![allow(clippy::result_large_err)]
+
+mod common;
+
+use std::path::PathBuf;
+
+use common::Echo;
+use prost_twirp::{serve_unix, ConnectServer, HyperClient, HyperServer, ProstTwirpError, Protocol, ServiceRequest,
+    UnixIncoming};
+
+fn socket_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("prost-twirp-{}-{}.sock", std::process::id(), name))
+}
+
+async fn echo(client: &HyperClient<prost_twirp::UnixConnector>, path: &str) -> Result<String, ProstTwirpError> {
+    client.go::<_, String>(path, ServiceRequest::new("hello".to_string())).await.map(|v| v.output)
+}
+
+#[tokio::test]
+async fn serves_and_calls_over_unix_socket() {
+    let path = socket_path("round-trip");
+    tokio::spawn(serve_unix(&path, HyperServer::new(Echo::default())).await.unwrap());
+
+    let client = HyperClient::new_unix(&path);
+    assert_eq!(echo(&client, "/twirp/test.Echo/Echo").await.unwrap(), "hello");
+    // Routing is the same as over TCP
+    match echo(&client, "/twirp/test.Echo/Missing").await.unwrap_err().root_err() {
+        ProstTwirpError::TwirpError(err) => assert_eq!(err.error_type, "bad_route"),
+        err => panic!("unexpected error {:?}", err),
+    }
+}
+
+#[tokio::test]
+async fn serves_connect_over_unix_socket() {
+    let path = socket_path("connect");
+    tokio::spawn(serve_unix(&path, ConnectServer::new(HyperServer::new(Echo::default()))).await.unwrap());
+
+    let client = HyperClient::new_unix(&path).with_protocol(Protocol::Connect);
+    assert_eq!(echo(&client, "/twirp/test.Echo/Echo").await.unwrap(), "hello");
+}
+
+#[tokio::test]
+async fn replaces_stale_sockets_and_cleans_up() {
+    let path = socket_path("stale");
+    drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+    assert!(path.exists());
+
+    let incoming = UnixIncoming::bind(&path).await.unwrap();
+    assert_eq!(incoming.path(), path);
+    drop(incoming);
+    assert!(!path.exists());
+
+    // Sockets that are still listened on are left alone
+    let incoming = UnixIncoming::bind(&path).await.unwrap();
+    assert_eq!(UnixIncoming::bind(&path).await.err().map(|v| v.kind()), Some(std::io::ErrorKind::AddrInUse));
+    assert!(path.exists());
+    drop(incoming);
+
+    // Anything but a socket is left alone
+    std::fs::write(&path, "not a socket").unwrap();
+    assert!(UnixIncoming::bind(&path).await.is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn fails_without_server() {
+    let client = HyperClient::new_unix(socket_path("missing"));
+    assert!(matches!(echo(&client, "/twirp/test.Echo/Echo").await, Err(ProstTwirpError::HyperError(_))));
+}